@@ -0,0 +1,511 @@
+use std::ffi::{OsStr, OsString};
+
+use crate::{
+    corewalker::{Parsed, Separators},
+    oschars, OptionTable,
+};
+
+/**
+Editor for argument lists, for wrappers that need to modify a few options
+and pass everything else on untouched.
+
+The arguments are split up the same way [`ArgWalker`][crate::ArgWalker] does
+it. An [`OptionTable`] is used to find out which options take a parameter, so
+that for example in `-vj4` and `--jobs 4`, the `4` is known to belong to
+`-j` or `--jobs`. Options are looked up by any of their names, so removing
+`--jobs` also removes `-j`. Flags that are not in the table are assumed not
+to take a parameter.
+
+Arguments that are not affected by any of the edits are emitted exactly as
+they were written. Combinations of short flags such as `-vj4` are rewritten
+only if one of their members is edited.
+
+Everything after `--` is considered a word.
+
+# Example
+
+```
+# use argwalker::{ArgEditor,OptionTable};
+let table = OptionTable::new()
+    .flag(&["--debug", "-d"])
+    .parameter(&["--jobs", "-j"]);
+let mut editor = ArgEditor::new(&table, &["-dvj8", "build", "--jobs", "2", "--release"]);
+assert_eq!(editor.find("-j"), vec![Some("8".as_ref()), Some("2".as_ref())]);
+editor.remove("--debug");
+editor.replace("--jobs", &["--jobs=4"]);
+editor.insert_after_word("build", &["--color=never"]);
+assert_eq!(
+    editor.into_args(),
+    vec!["-v", "--jobs=4", "build", "--color=never", "--jobs=4", "--release"]
+);
+```
+*/
+#[derive(Debug, Clone)]
+pub struct ArgEditor {
+    table: OptionTable,
+    units: Vec<Unit>,
+}
+
+/// One or more original arguments that belong together, for example a word,
+/// a long flag with its parameter or a combination of short flags.
+#[derive(Debug, Clone)]
+struct Unit {
+    /// The original text, if the unit has not been modified.
+    original: Option<Vec<OsString>>,
+    members: Vec<Member>,
+}
+
+#[derive(Debug, Clone)]
+enum Member {
+    Word(OsString),
+    /// A short or long flag as it was written.
+    Flag {
+        flag: String,
+        parameter: Option<Parameter>,
+    },
+    /// Anything we cannot or need not interpret, such as the undecodable
+    /// tail of a combination of short flags.
+    Opaque(OsString),
+    /// Arguments inserted by the caller.
+    Inserted(Vec<OsString>),
+}
+
+#[derive(Debug, Clone)]
+enum Parameter {
+    /// `-j4` or `--jobs=4`
+    Attached(OsString),
+    /// `-j 4` or `--jobs 4`
+    Separate(OsString),
+}
+
+impl ArgEditor {
+    /// Split the arguments into units that can be edited, using `table` to
+    /// find out which options take parameters.
+    pub fn new<S, T>(table: &OptionTable, args: T) -> Self
+    where
+        T: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut args = args
+            .into_iter()
+            .map(|a| a.as_ref().to_os_string())
+            .peekable();
+        let separators = table.flag_separators();
+        let mut units = vec![];
+        while let Some(arg) = args.next() {
+            let mut original = vec![arg.clone()];
+            let mut members = vec![];
+            let literal_rest = arg == "--";
+            let wants_parameter =
+                match Parsed::with_separators(oschars::as_bytes(&arg), &separators) {
+                    Parsed::Arg(word) => {
+                        members.push(Member::Word(oschars::from_vec(word.into_owned())));
+                        false
+                    }
+                    Parsed::Invalid(s) => {
+                        members.push(Member::Opaque(oschars::from_vec(s.into_owned())));
+                        false
+                    }
+                    Parsed::Long {
+                        flag, parameter, ..
+                    } => {
+                        let wants =
+                            parameter.is_none() && table.takes_parameter(&flag) == Some(true);
+                        let parameter = parameter
+                            .map(|p| Parameter::Attached(oschars::from_vec(p.into_owned())));
+                        members.push(Member::Flag {
+                            flag: flag.into_owned(),
+                            parameter,
+                        });
+                        wants
+                    }
+                    Parsed::Short { flags } => {
+                        split_short(table, &separators, &flags, None, &mut members)
+                    }
+                    Parsed::ShortTail { flags, tail } => split_short(
+                        table,
+                        &separators,
+                        &flags,
+                        Some(oschars::from_vec(tail.into_owned())),
+                        &mut members,
+                    ),
+                };
+            if wants_parameter {
+                let next_is_word = args
+                    .peek()
+                    .is_some_and(|a| matches!(Parsed::new(oschars::as_bytes(a)), Parsed::Arg(_)));
+                if next_is_word {
+                    let parm = args.next().unwrap();
+                    original.push(parm.clone());
+                    if let Some(Member::Flag { parameter, .. }) = members.last_mut() {
+                        *parameter = Some(Parameter::Separate(parm));
+                    }
+                }
+            }
+            units.push(Unit {
+                original: Some(original),
+                members,
+            });
+            if literal_rest {
+                for word in args.by_ref() {
+                    units.push(Unit {
+                        original: Some(vec![word.clone()]),
+                        members: vec![Member::Word(word)],
+                    });
+                }
+            }
+        }
+
+        ArgEditor {
+            table: table.clone(),
+            units,
+        }
+    }
+
+    fn matches(&self, member: &Member, name: &str) -> bool {
+        match member {
+            Member::Flag { flag, .. } => {
+                match (self.table.canonical(flag), self.table.canonical(name)) {
+                    (Some(a), Some(b)) => a == b,
+                    _ => flag == name,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Find all occurrences of the option with the given name, under any of
+    /// its names. Returns the parameter of each occurrence, or `None` if it
+    /// has none.
+    pub fn find(&self, name: &str) -> Vec<Option<&OsStr>> {
+        self.units
+            .iter()
+            .flat_map(|u| &u.members)
+            .filter(|m| self.matches(m, name))
+            .map(|m| match m {
+                Member::Flag {
+                    parameter: Some(Parameter::Attached(p)),
+                    ..
+                }
+                | Member::Flag {
+                    parameter: Some(Parameter::Separate(p)),
+                    ..
+                } => Some(p.as_os_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Remove all occurrences of the option with the given name, including
+    /// their parameters. Returns the number of occurrences removed.
+    pub fn remove(&mut self, name: &str) -> usize {
+        self.replace(name, &[] as &[&str])
+    }
+
+    /// Replace every occurrence of the option with the given name, including
+    /// its parameter, with the given arguments. If the option is part of a
+    /// combination of short flags, the combination is split up around it.
+    /// Returns the number of occurrences replaced.
+    pub fn replace<S: AsRef<OsStr>>(&mut self, name: &str, replacement: &[S]) -> usize {
+        let replacement: Vec<OsString> = replacement
+            .iter()
+            .map(|r| r.as_ref().to_os_string())
+            .collect();
+        let mut count = 0;
+        for i in 0..self.units.len() {
+            for j in 0..self.units[i].members.len() {
+                if self.matches(&self.units[i].members[j], name) {
+                    let unit = &mut self.units[i];
+                    unit.members[j] = Member::Inserted(replacement.clone());
+                    unit.original = None;
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Insert arguments after the first occurrence of the given word, for
+    /// example a subcommand. Returns `false` if the word does not occur.
+    pub fn insert_after_word<S: AsRef<OsStr>>(&mut self, word: &str, args: &[S]) -> bool {
+        let pos = self.units.iter().position(|u| {
+            u.members
+                .iter()
+                .any(|m| matches!(m, Member::Word(w) if w == word))
+        });
+        match pos {
+            Some(pos) => {
+                self.units.insert(pos + 1, Unit::inserted(args));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Insert arguments at the start.
+    pub fn insert_front<S: AsRef<OsStr>>(&mut self, args: &[S]) {
+        self.units.insert(0, Unit::inserted(args));
+    }
+
+    /// Append arguments at the end.
+    pub fn push<S: AsRef<OsStr>>(&mut self, args: &[S]) {
+        self.units.push(Unit::inserted(args));
+    }
+
+    /// The edited arguments.
+    pub fn to_args(&self) -> Vec<OsString> {
+        let mut out = vec![];
+        for unit in &self.units {
+            unit.render(&self.table, &mut out);
+        }
+        out
+    }
+
+    /// The edited arguments.
+    pub fn into_args(self) -> Vec<OsString> {
+        self.to_args()
+    }
+}
+
+/// Split a combination of short flags into members. Prefix options such as
+/// `-Wl,` in `-vWl,-z` are matched like the walker does, the longest one
+/// wins. Returns `true` if the last flag still wants a separate parameter.
+fn split_short(
+    table: &OptionTable,
+    separators: &Separators,
+    flags: &str,
+    tail: Option<OsString>,
+    members: &mut Vec<Member>,
+) -> bool {
+    let mut offset = 1;
+    while offset < flags.len() {
+        let head = format!("-{}", &flags[offset..]);
+        let (len, separator) = if tail.is_none() && table.takes_parameter(&head).is_some() {
+            // the rest is an option by itself, such as a prefix option
+            // with nothing after it
+            (head.len(), None)
+        } else {
+            separators
+                .split_flag(&head, tail.is_some())
+                .unwrap_or((1 + head[1..].chars().next().unwrap().len_utf8(), None))
+        };
+        let flag = head[..len].to_string();
+        offset += len - 1;
+        if table.takes_parameter(&flag) == Some(true) {
+            let start = len + separator.map_or(0, char::len_utf8);
+            let mut parm = OsString::from(&head[start..]);
+            if let Some(tail) = &tail {
+                parm.push(tail);
+            }
+            let wants = parm.is_empty();
+            let parameter = if wants {
+                None
+            } else {
+                Some(Parameter::Attached(parm))
+            };
+            members.push(Member::Flag { flag, parameter });
+            return wants;
+        }
+        members.push(Member::Flag {
+            flag,
+            parameter: None,
+        });
+    }
+    members.extend(tail.map(Member::Opaque));
+    false
+}
+
+impl Unit {
+    fn inserted<S: AsRef<OsStr>>(args: &[S]) -> Self {
+        let args = args.iter().map(|a| a.as_ref().to_os_string()).collect();
+        Unit {
+            original: None,
+            members: vec![Member::Inserted(args)],
+        }
+    }
+
+    fn render(&self, table: &OptionTable, out: &mut Vec<OsString>) {
+        if let Some(original) = &self.original {
+            out.extend(original.iter().cloned());
+            return;
+        }
+
+        // Short flags are combined again as far as possible
+        let mut cluster: Option<OsString> = None;
+        for member in &self.members {
+            match member {
+                Member::Flag { flag, parameter } if !flag.starts_with("--") => {
+                    let c = cluster.get_or_insert_with(|| OsString::from("-"));
+                    c.push(&flag[1..]);
+                    match parameter {
+                        Some(Parameter::Attached(p)) => {
+                            c.push(p);
+                            out.extend(cluster.take());
+                        }
+                        Some(Parameter::Separate(p)) => {
+                            out.extend(cluster.take());
+                            out.push(p.clone());
+                        }
+                        None => {}
+                    }
+                }
+                // removed, the cluster can continue
+                Member::Inserted(args) if args.is_empty() => {}
+                Member::Opaque(s) if cluster.is_some() => {
+                    let mut c = cluster.take().unwrap();
+                    c.push(s);
+                    out.push(c);
+                }
+                _ => {
+                    out.extend(cluster.take());
+                    match member {
+                        Member::Word(w) | Member::Opaque(w) => out.push(w.clone()),
+                        Member::Inserted(args) => out.extend(args.iter().cloned()),
+                        Member::Flag { flag, parameter } => match parameter {
+                            None => out.push(OsString::from(flag)),
+                            Some(Parameter::Attached(p)) => {
+                                let mut s = OsString::from(flag);
+                                s.push(table.long_separator(flag));
+                                s.push(p);
+                                out.push(s);
+                            }
+                            Some(Parameter::Separate(p)) => {
+                                out.push(OsString::from(flag));
+                                out.push(p.clone());
+                            }
+                        },
+                    }
+                }
+            }
+        }
+        out.extend(cluster.take());
+    }
+}
+
+#[test]
+fn test_editor() {
+    use crate::oschars::bad_text as bad;
+
+    let table = OptionTable::new()
+        .flag(&["--verbose", "-v"])
+        .flag(&["--debug", "-d"])
+        .parameter(&["--jobs", "-j"]);
+    let oss = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+    let edit = |args: &[OsString], f: &dyn Fn(&mut ArgEditor)| {
+        let mut editor = ArgEditor::new(&table, args);
+        f(&mut editor);
+        editor.into_args()
+    };
+
+    // nothing changed means nothing changed
+    let args = oss(&["-vdj", "4", "--jobs=4", "-xyz", "---", "--", "-v", "--jobs"]);
+    assert_eq!(edit(&args, &|_| {}), args);
+
+    // removing from clusters, long flags and both kinds of parameters
+    assert_eq!(
+        edit(
+            &oss(&["-vdj4", "-dv", "-d", "--debug", "--jobs", "8", "-xdy", "--", "-d"]),
+            &|e| assert_eq!(e.remove("-d"), 5)
+        ),
+        oss(&["-vj4", "-v", "--jobs", "8", "-xy", "--", "-d"])
+    );
+    assert_eq!(
+        edit(&oss(&["-vdj4", "-j", "4", "--jobs=2", "x", "-j"]), &|e| {
+            assert_eq!(e.remove("--jobs"), 4)
+        }),
+        oss(&["-vd", "x"])
+    );
+
+    // replacing within a cluster splits it up, the d in -jd is a parameter
+    assert_eq!(
+        edit(&oss(&["-vdj4", "-jd"]), &|e| {
+            assert_eq!(e.replace("--debug", &["--no-debug"]), 1);
+        }),
+        oss(&["-v", "--no-debug", "-j4", "-jd"])
+    );
+
+    // parameters are found in every form
+    let editor = ArgEditor::new(
+        &table,
+        oss(&["-vj1", "-j", "2", "--jobs=3", "--jobs", "4", "-j"]),
+    );
+    let found: Vec<Option<&OsStr>> = ["1", "2", "3", "4"]
+        .iter()
+        .map(|s| Some(OsStr::new(s)))
+        .chain(Some(None))
+        .collect();
+    assert_eq!(editor.find("--jobs"), found);
+
+    // inserting
+    assert_eq!(
+        edit(&oss(&["-v", "build", "-j", "build"]), &|e| {
+            assert!(e.insert_after_word("build", &["--color=never"]));
+            assert!(!e.insert_after_word("test", &["--color=never"]));
+            e.insert_front(&["+nightly"]);
+            e.push(&["--", "x"]);
+        }),
+        oss(&[
+            "+nightly",
+            "-v",
+            "build",
+            "--color=never",
+            "-j",
+            "build",
+            "--",
+            "x"
+        ])
+    );
+
+    // non-unicode is preserved, both when untouched and when rewritten
+    let args = vec![bad("-vxdj"), bad("-dv"), bad("--jobs=")];
+    assert_eq!(edit(&args, &|_| {}), args);
+    assert_eq!(
+        edit(&args, &|e| {
+            e.remove("-v");
+        }),
+        vec![bad("-xdj"), bad("-d"), bad("--jobs=")]
+    );
+
+    // prefix options are not split into short flags
+    let table = OptionTable::new().flag(&["-v"]).prefix(&["-W"]);
+    let mut editor = ArgEditor::new(&table, oss(&["-Wall", "-vWextra", "-Werror"]));
+    let found = ["all", "extra", "error"].map(|s| Some(OsStr::new(s)));
+    assert_eq!(editor.find("-W"), found);
+    editor.remove("-v");
+    assert_eq!(editor.into_args(), oss(&["-Wall", "-Wextra", "-Werror"]));
+
+    // within a cluster the longest prefix wins as well
+    let table = OptionTable::new()
+        .flag(&["-v"])
+        .flag(&["-l"])
+        .prefix(&["-W"])
+        .prefix(&["-Wl,"]);
+    let mut editor = ArgEditor::new(&table, oss(&["-vWl,-z", "-vWall", "-lWl,", "x"]));
+    assert_eq!(
+        editor.find("-Wl,"),
+        [Some(OsStr::new("-z")), Some(OsStr::new("x"))]
+    );
+    assert_eq!(editor.find("-W"), [Some(OsStr::new("all"))]);
+    editor.remove("-v");
+    editor.remove("-l");
+    assert_eq!(editor.into_args(), oss(&["-Wl,-z", "-Wall", "-Wl,", "x"]));
+
+    // rewritten long flags use the first separator of the option
+    let table = OptionTable::new()
+        .parameter(&["--define", "-D"])
+        .separators(&[':', '='])
+        .parameter(&["--opt"])
+        .separators(&[]);
+    let mut out = vec![];
+    for flag in ["--define", "--opt", "--other"] {
+        let unit = Unit {
+            original: None,
+            members: vec![Member::Flag {
+                flag: flag.to_string(),
+                parameter: Some(Parameter::Attached("X".into())),
+            }],
+        };
+        unit.render(&table, &mut out);
+    }
+    assert_eq!(out, oss(&["--define:X", "--optX", "--other=X"]));
+}
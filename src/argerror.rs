@@ -1,11 +1,15 @@
-use std::{error, ffi::OsString};
 use std::fmt;
+use std::{error, ffi::OsString};
 
+use crate::escape::Escaped;
+use crate::oschars;
+use crate::shell_words::quote_arg;
+use crate::ByteError;
 
 /**
 Error type for `ArgWalker`.
 */
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub enum ArgError {
     /// Argument could not be decoded as valid Unicode.
     InvalidUnicode(OsString),
@@ -16,16 +20,186 @@ pub enum ArgError {
     /// Returned by [`ArgWalker::parameter`] and [`ArgWalker::parameter_os`]
     /// if no parameter is available, for example on `-f` in  `-f -v`.
     ParameterMissing(String),
+    /// Returned by [`ArgWalker::finish`] if not all arguments have been
+    /// taken. Holds the leftover arguments as they were originally written,
+    /// except that any part of a combination of short flags that has already
+    /// been taken is left out. For example, `-x` if only the `-v` of `-vx`
+    /// has been taken.
+    UnconsumedArguments(Vec<OsString>),
+    /// Returned by [`OptionTable::normalize`][crate::OptionTable::normalize]
+    /// for a flag that is not in the table. Also available to applications
+    /// that want to report a flag they do not recognize, for example through
+    /// [`ArgWalker::record_error`].
+    UnknownFlag(String),
+    /// Returned by [`ArgWalker::from_shell_words`] and the functions in
+    /// [`shell_words`][crate::shell_words] if a quote is not closed.
+    /// Holds the quote character and its offset in the text.
+    UnterminatedQuote(char, usize),
+    /// Returned by [`ArgWalker::finish`] if more than one error has been
+    /// recorded in recovering mode, see [`ArgWalker::recover_errors`].
+    Multiple(Vec<ArgError>),
+    /// Returned by [`escape::unescape`][crate::escape::unescape] if an
+    /// escape sequence is not recognized. Holds its offset in the text.
+    InvalidEscape(usize),
+    /// Returned by [`ArgWalker::take_item`] and [`ArgWalker::take_item_os`]
+    /// if a short flag consists of nothing but a combining mark, for
+    /// example the accent in `-\u{301}v`. Only when splitting grapheme
+    /// clusters, see [`ArgWalker::split_graphemes`].
+    LoneCombiningMark(String),
+    /// A word that starts with a Unicode look-alike of a dash or a quote,
+    /// as happens when a command is copied from a document, for example
+    /// `\u{2013}verbose` with an en dash. Holds the word and its spelling
+    /// in ASCII. Only if enabled, see [`ArgWalker::look_alike_policy`].
+    LookAlikeDash(String, String),
+    /// A long flag that makes no sense, such as `---x`, `--=x` or a name
+    /// with whitespace or control characters in it. Holds the argument as
+    /// written. Only if enabled, see [`ArgWalker::strict_flags`].
+    MalformedFlag(String),
 }
 
 impl fmt::Display for ArgError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ArgError::InvalidUnicode(a) => write!(f, "invalid unicode in argument {:?}", a),
-            ArgError::UnexpectedParameter(flag) => write!(f, "unexpected parameter for flag {}", flag),
+            ArgError::InvalidUnicode(a) => {
+                write!(f, "invalid unicode in argument {}", Escaped::os(a))
+            }
+            ArgError::UnexpectedParameter(flag) => {
+                write!(f, "unexpected parameter for flag {}", flag)
+            }
             ArgError::ParameterMissing(flag) => write!(f, "parameter missing for flag {}", flag),
+            ArgError::UnconsumedArguments(args) => {
+                write!(f, "unexpected argument")?;
+                if args.len() > 1 {
+                    write!(f, "s")?;
+                }
+                for a in args {
+                    write!(f, " {}", quote_arg(a))?;
+                }
+                Ok(())
+            }
+            ArgError::UnknownFlag(flag) => write!(f, "unknown flag {}", flag),
+            ArgError::UnterminatedQuote(quote, offset) => {
+                write!(f, "unterminated {} at offset {}", quote, offset)
+            }
+            ArgError::Multiple(errors) => {
+                let mut sep = "";
+                for e in errors {
+                    write!(f, "{}{}", sep, e)?;
+                    sep = "\n";
+                }
+                Ok(())
+            }
+            ArgError::InvalidEscape(offset) => write!(f, "invalid escape at offset {}", offset),
+            ArgError::LoneCombiningMark(flag) => {
+                write!(f, "flag {} is a lone combining mark", flag)
+            }
+            ArgError::LookAlikeDash(word, suggestion) => write!(
+                f,
+                "argument {} starts with a look-alike of a dash or quote, did you mean {}?",
+                word, suggestion
+            ),
+            ArgError::MalformedFlag(arg) => write!(f, "malformed flag {}", arg),
+        }
+    }
+}
+
+/// Like the derived implementation, but with arguments that are not valid
+/// Unicode escaped rather than replaced, see [`escape`][crate::escape].
+impl fmt::Debug for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgError::InvalidUnicode(a) => f
+                .debug_tuple("InvalidUnicode")
+                .field(&Escaped::os(a))
+                .finish(),
+            ArgError::UnexpectedParameter(flag) => {
+                f.debug_tuple("UnexpectedParameter").field(flag).finish()
+            }
+            ArgError::ParameterMissing(flag) => {
+                f.debug_tuple("ParameterMissing").field(flag).finish()
+            }
+            ArgError::UnconsumedArguments(args) => f
+                .debug_tuple("UnconsumedArguments")
+                .field(&args.iter().map(|a| Escaped::os(a)).collect::<Vec<_>>())
+                .finish(),
+            ArgError::UnknownFlag(flag) => f.debug_tuple("UnknownFlag").field(flag).finish(),
+            ArgError::UnterminatedQuote(quote, offset) => f
+                .debug_tuple("UnterminatedQuote")
+                .field(quote)
+                .field(offset)
+                .finish(),
+            ArgError::Multiple(errors) => f.debug_tuple("Multiple").field(errors).finish(),
+            ArgError::InvalidEscape(offset) => {
+                f.debug_tuple("InvalidEscape").field(offset).finish()
+            }
+            ArgError::LoneCombiningMark(flag) => {
+                f.debug_tuple("LoneCombiningMark").field(flag).finish()
+            }
+            ArgError::LookAlikeDash(word, suggestion) => f
+                .debug_tuple("LookAlikeDash")
+                .field(word)
+                .field(suggestion)
+                .finish(),
+            ArgError::MalformedFlag(arg) => f.debug_tuple("MalformedFlag").field(arg).finish(),
+        }
+    }
+}
+
+impl error::Error for ArgError {}
+
+impl From<ByteError> for ArgError {
+    fn from(err: ByteError) -> Self {
+        match err {
+            ByteError::InvalidUnicode(a) => ArgError::InvalidUnicode(oschars::from_vec(a)),
+            ByteError::UnexpectedParameter(flag) => ArgError::UnexpectedParameter(flag),
+            ByteError::ParameterMissing(flag) => ArgError::ParameterMissing(flag),
+            ByteError::UnconsumedArguments(args) => {
+                ArgError::UnconsumedArguments(args.into_iter().map(oschars::from_vec).collect())
+            }
+            ByteError::InvalidEscape(offset) => ArgError::InvalidEscape(offset),
+            ByteError::LoneCombiningMark(flag) => ArgError::LoneCombiningMark(flag),
+            ByteError::LookAlikeDash(word, suggestion) => ArgError::LookAlikeDash(word, suggestion),
+            ByteError::MalformedFlag(arg) => ArgError::MalformedFlag(arg),
         }
     }
 }
 
-impl error::Error for ArgError {}
\ No newline at end of file
+#[test]
+fn test_display() {
+    let err = ArgError::UnconsumedArguments(vec!["foo".into(), "red apple".into()]);
+    assert_eq!(err.to_string(), "unexpected arguments foo 'red apple'");
+
+    #[cfg(unix)]
+    {
+        let err = ArgError::InvalidUnicode(crate::oschars::bad_text("banana"));
+        assert_eq!(err.to_string(), r"invalid unicode in argument banana\xFF");
+    }
+
+    let err = ArgError::LookAlikeDash("\u{2013}verbose".into(), "--verbose".into());
+    assert_eq!(
+        err.to_string(),
+        "argument \u{2013}verbose starts with a look-alike of a dash or quote, \
+         did you mean --verbose?"
+    );
+}
+
+#[test]
+fn test_debug() {
+    let err = ArgError::ParameterMissing("-f".into());
+    assert_eq!(format!("{:?}", err), r#"ParameterMissing("-f")"#);
+    let err = ArgError::UnterminatedQuote('"', 3);
+    assert_eq!(format!("{:?}", err), r#"UnterminatedQuote('"', 3)"#);
+
+    #[cfg(unix)]
+    {
+        use crate::oschars::bad_text as bad;
+        let err = ArgError::Multiple(vec![
+            ArgError::InvalidUnicode(bad("banana")),
+            ArgError::UnconsumedArguments(vec!["foo".into(), bad("x")]),
+        ]);
+        assert_eq!(
+            format!("{:?}", err),
+            r#"Multiple([InvalidUnicode("banana\xFF"), UnconsumedArguments(["foo", "x\xFF"])])"#
+        );
+    }
+}
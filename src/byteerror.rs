@@ -0,0 +1,108 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use crate::escape::Escaped;
+
+/**
+Error type for [`ByteWalker`][crate::ByteWalker].
+
+The counterpart of [`ArgError`][crate::ArgError] for arguments given as bytes.
+*/
+#[derive(Clone, PartialEq, Eq)]
+pub enum ByteError {
+    /// Flag could not be decoded as valid UTF-8.
+    InvalidUnicode(Vec<u8>),
+    /// Returned by [`ByteWalker::take_item`][crate::ByteWalker::take_item]
+    /// if the previous long option has a parameter which has not been
+    /// retrieved, for example `--fruit=banana`.
+    UnexpectedParameter(String),
+    /// Returned by [`ByteWalker::required_parameter`][crate::ByteWalker::required_parameter]
+    /// if no parameter is available, for example on `-f` in  `-f -v`.
+    ParameterMissing(String),
+    /// Returned by [`ByteWalker::finish`][crate::ByteWalker::finish] if not
+    /// all arguments have been taken, see [`ArgError::UnconsumedArguments`][crate::ArgError::UnconsumedArguments].
+    UnconsumedArguments(Vec<Vec<u8>>),
+    /// Returned by [`escape::unescape_bytes`][crate::escape::unescape_bytes]
+    /// if an escape sequence is not recognized. Holds its offset.
+    InvalidEscape(usize),
+    /// Short flag that is a lone combining mark, see
+    /// [`ArgError::LoneCombiningMark`][crate::ArgError::LoneCombiningMark].
+    LoneCombiningMark(String),
+    /// Word that starts with a look-alike of a dash or a quote, see
+    /// [`ArgError::LookAlikeDash`][crate::ArgError::LookAlikeDash].
+    LookAlikeDash(String, String),
+    /// Long flag that makes no sense, see
+    /// [`ArgError::MalformedFlag`][crate::ArgError::MalformedFlag].
+    MalformedFlag(String),
+}
+
+impl fmt::Debug for ByteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // show the bytes as text rather than as a list of numbers
+        match self {
+            ByteError::InvalidUnicode(a) => f
+                .debug_tuple("InvalidUnicode")
+                .field(&Escaped::bytes(a))
+                .finish(),
+            ByteError::UnexpectedParameter(flag) => {
+                f.debug_tuple("UnexpectedParameter").field(flag).finish()
+            }
+            ByteError::ParameterMissing(flag) => {
+                f.debug_tuple("ParameterMissing").field(flag).finish()
+            }
+            ByteError::UnconsumedArguments(args) => f
+                .debug_tuple("UnconsumedArguments")
+                .field(&args.iter().map(|a| Escaped::bytes(a)).collect::<Vec<_>>())
+                .finish(),
+            ByteError::InvalidEscape(offset) => {
+                f.debug_tuple("InvalidEscape").field(offset).finish()
+            }
+            ByteError::LoneCombiningMark(flag) => {
+                f.debug_tuple("LoneCombiningMark").field(flag).finish()
+            }
+            ByteError::LookAlikeDash(word, suggestion) => f
+                .debug_tuple("LookAlikeDash")
+                .field(word)
+                .field(suggestion)
+                .finish(),
+            ByteError::MalformedFlag(arg) => f.debug_tuple("MalformedFlag").field(arg).finish(),
+        }
+    }
+}
+
+impl fmt::Display for ByteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ByteError::InvalidUnicode(a) => {
+                write!(f, "invalid unicode in argument {}", Escaped::bytes(a))
+            }
+            ByteError::UnexpectedParameter(flag) => {
+                write!(f, "unexpected parameter for flag {}", flag)
+            }
+            ByteError::ParameterMissing(flag) => write!(f, "parameter missing for flag {}", flag),
+            ByteError::UnconsumedArguments(args) => {
+                write!(f, "unexpected argument")?;
+                if args.len() > 1 {
+                    write!(f, "s")?;
+                }
+                for a in args {
+                    write!(f, " {}", Escaped::bytes(a))?;
+                }
+                Ok(())
+            }
+            ByteError::InvalidEscape(offset) => write!(f, "invalid escape at offset {}", offset),
+            ByteError::LoneCombiningMark(flag) => {
+                write!(f, "flag {} is a lone combining mark", flag)
+            }
+            ByteError::LookAlikeDash(word, suggestion) => write!(
+                f,
+                "argument {} starts with a look-alike of a dash or quote, did you mean {}?",
+                word, suggestion
+            ),
+            ByteError::MalformedFlag(arg) => write!(f, "malformed flag {}", arg),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ByteError {}
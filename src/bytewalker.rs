@@ -0,0 +1,218 @@
+use alloc::{borrow::Cow, boxed::Box, vec::Vec};
+use core::fmt;
+
+use crate::corewalker::CoreWalker;
+use crate::escape::Escaped;
+use crate::ByteError;
+
+/**
+Item returned from [`ByteWalker::take_item`].
+*/
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ByteItem<'a> {
+    Flag(&'a str),
+    /// A flag that is not valid UTF-8, only returned if enabled with
+    /// [`ByteWalker::allow_invalid_flags`].
+    FlagBytes(&'a [u8]),
+    Word(&'a [u8]),
+}
+
+impl fmt::Debug for ByteItem<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ByteItem::Flag(flag) => f.debug_tuple("Flag").field(flag).finish(),
+            ByteItem::FlagBytes(flag) => f
+                .debug_tuple("FlagBytes")
+                .field(&Escaped::bytes(flag))
+                .finish(),
+            ByteItem::Word(word) => f.debug_tuple("Word").field(&Escaped::bytes(word)).finish(),
+        }
+    }
+}
+
+/**
+Command line argument helper for arguments given as bytes.
+
+This is the part of [`ArgWalker`][crate::ArgWalker] that does not need the
+standard library, so it is also available without the default `std` feature,
+for example on embedded targets that receive their command line over a
+serial line. Flags must be valid UTF-8 but words and parameters can be
+arbitrary bytes.
+
+The arguments are borrowed, not copied.
+
+# Example
+
+```
+# use argwalker::{ByteWalker,ByteError,ByteItem};
+# fn main() -> Result<(), ByteError> {
+let args: &[&[u8]] = &[b"-vfbanana", b"file\xFF"];
+let mut w = ByteWalker::new(args);
+assert_eq!(w.take_item(), Ok(Some(ByteItem::Flag("-v"))));
+assert_eq!(w.take_item(), Ok(Some(ByteItem::Flag("-f"))));
+assert_eq!(&*w.required_parameter(true)?, b"banana");
+assert_eq!(w.take_item(), Ok(Some(ByteItem::Word(b"file\xFF"))));
+w.finish()?;
+#    Ok(())
+# }
+```
+*/
+#[derive(Debug)]
+pub struct ByteWalker<'a> {
+    core: CoreWalker<'a>,
+}
+
+impl<'a> ByteWalker<'a> {
+    /// Construct a new [`ByteWalker`] that borrows its arguments.
+    pub fn new<S: AsRef<[u8]>>(args: &'a [S]) -> Self {
+        ByteWalker {
+            core: CoreWalker::borrowed(args),
+        }
+    }
+
+    /// Construct a new [`ByteWalker`] that reads its arguments on demand, see
+    /// [`ArgWalker::new_lazy`][crate::ArgWalker::new_lazy].
+    pub fn new_lazy<T>(args: T) -> Self
+    where
+        T: IntoIterator<Item = Vec<u8>>,
+        T::IntoIter: Send + 'a,
+    {
+        ByteWalker {
+            core: CoreWalker::from_source(Box::new(args.into_iter())),
+        }
+    }
+
+    /// Allow flags that are not valid UTF-8, see
+    /// [`ArgWalker::allow_invalid_flags`][crate::ArgWalker::allow_invalid_flags].
+    pub fn allow_invalid_flags(&mut self, enabled: bool) {
+        self.core.allow_invalid_flags(enabled);
+    }
+
+    /// Split combinations of short flags into grapheme clusters, see
+    /// [`ArgWalker::split_graphemes`][crate::ArgWalker::split_graphemes].
+    pub fn split_graphemes(&mut self, enabled: bool) {
+        self.core.split_graphemes(enabled);
+    }
+
+    /// Reject words that start with a look-alike of a dash or a quote with
+    /// [`ByteError::LookAlikeDash`], see
+    /// [`ArgWalker::look_alike_policy`][crate::ArgWalker::look_alike_policy].
+    pub fn reject_look_alikes(&mut self, enabled: bool) {
+        self.core.reject_look_alikes(enabled);
+    }
+
+    /// Reject long flags that make no sense, see
+    /// [`ArgWalker::strict_flags`][crate::ArgWalker::strict_flags].
+    pub fn strict_flags(&mut self, enabled: bool) {
+        self.core.strict_flags(enabled);
+    }
+
+    /// Treat `-f=banana` as `-f banana`, see
+    /// [`ArgWalker::allow_short_equals`][crate::ArgWalker::allow_short_equals].
+    pub fn allow_short_equals(&mut self, enabled: bool) {
+        self.core.allow_short_equals(enabled);
+    }
+
+    /// Set the characters that separate a long flag from its parameter, see
+    /// [`ArgWalker::long_separators`][crate::ArgWalker::long_separators].
+    pub fn long_separators(&mut self, separators: &[char]) {
+        self.core.long_separators(separators);
+    }
+
+    /// Set the separators of a single long flag, see
+    /// [`ArgWalker::option_separators`][crate::ArgWalker::option_separators].
+    pub fn option_separators(&mut self, flag: &str, separators: &[char]) {
+        self.core.flag_separators(flag, separators);
+    }
+
+    /// Declare a prefix option such as `-W` in `-Wall`, see
+    /// [`ArgWalker::prefix_option`][crate::ArgWalker::prefix_option].
+    pub fn prefix_option(&mut self, prefix: &str) {
+        self.core.prefix_option(prefix);
+    }
+
+    /// Look at the upcoming item without moving on to the next.
+    pub fn peek_item(&self) -> Result<Option<ByteItem<'_>>, ByteError> {
+        self.core.upcoming()
+    }
+
+    /// Retrieve the upcoming item and move on to the next.
+    pub fn take_item(&mut self) -> Result<Option<ByteItem<'_>>, ByteError> {
+        self.core.advance()
+    }
+
+    /// Returns `true` if a parameter is available, see
+    /// [`ArgWalker::has_parameter`][crate::ArgWalker::has_parameter].
+    pub fn has_parameter(&self, free_standing: bool) -> bool {
+        self.core.can_parameter()
+            || (free_standing && matches!(self.core.upcoming(), Ok(Some(ByteItem::Word(_)))))
+    }
+
+    /// Returns the parameter of the current flag, if any, see
+    /// [`ArgWalker::parameter`][crate::ArgWalker::parameter]. The parameter
+    /// is borrowed from the arguments unless it belongs to a short flag and
+    /// is not valid UTF-8.
+    pub fn parameter(&mut self, free_standing: bool) -> Result<Option<Cow<'a, [u8]>>, ByteError> {
+        if let Some(p) = self.core.parameter() {
+            return Ok(Some(p));
+        }
+
+        if !free_standing {
+            return Ok(None);
+        }
+
+        match self.core.upcoming()? {
+            Some(ByteItem::Word(_)) => {
+                self.core.advance()?;
+            }
+            _ => return Ok(None),
+        }
+        let word = self.core.current_word();
+        assert!(word.is_some(), "upcoming said Word but got something else");
+        Ok(word)
+    }
+
+    /// Like [`.parameter()`][ByteWalker::parameter] but returns
+    /// [`ByteError::ParameterMissing`] if there is no parameter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the current item is not a flag.
+    pub fn required_parameter(&mut self, free_standing: bool) -> Result<Cow<'a, [u8]>, ByteError> {
+        if let Some(p) = self.parameter(free_standing)? {
+            return Ok(p);
+        }
+
+        if let Some(flag) = self.core.current_flag() {
+            Err(ByteError::ParameterMissing(flag))
+        } else {
+            panic!(".required_parameter can only be called right after a flag")
+        }
+    }
+
+    /// Take all remaining arguments exactly as they were written, see
+    /// [`ArgWalker::take_rest_os`][crate::ArgWalker::take_rest_os].
+    pub fn take_rest(&mut self) -> Result<Vec<Vec<u8>>, ByteError> {
+        self.core.take_rest()
+    }
+
+    /// Append the current item to `forwarded` exactly as it was written, see
+    /// [`ArgWalker::forward`][crate::ArgWalker::forward].
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no current item, see
+    /// [`ArgWalker::forward`][crate::ArgWalker::forward].
+    pub fn forward(&mut self, forwarded: &mut Vec<Vec<u8>>) {
+        match self.core.take_original() {
+            Some(text) => forwarded.push(text),
+            None => panic!(".forward() can only be called right after an item has been taken"),
+        }
+    }
+
+    /// Verify that all arguments have been consumed, see
+    /// [`ArgWalker::finish`][crate::ArgWalker::finish].
+    pub fn finish(self) -> Result<(), ByteError> {
+        self.core.check_finished()
+    }
+}
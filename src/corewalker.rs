@@ -1,289 +1,929 @@
-use std::{
-    ffi::{OsStr, OsString},
-    mem,
-    ops::Not,
+//! The state machine behind [`ArgWalker`][crate::ArgWalker] and
+//! [`ByteWalker`][crate::ByteWalker]. It only needs `alloc` and works on
+//! bytes: the raw bytes of the arguments on Unix and their WTF-8 encoding
+//! on Windows. Splitting only ever happens right next to valid UTF-8, so
+//! the pieces can be turned back into `OsStr`s.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::VecDeque,
+    format,
+    string::{String, ToString},
+    vec::Vec,
 };
+use core::{fmt, mem, str};
 
-use crate::{item::ItemOs, ArgError};
+use crate::{grapheme, ByteError, ByteItem};
 
-type ArgResult<T> = Result<T, ArgError>;
+type ByteResult<T> = Result<T, ByteError>;
 
-/// Intermediate representation of a command line argument.
+/// Intermediate representation of a command line argument. The text is
+/// borrowed from the original argument where possible.
 #[derive(Debug, Clone, PartialEq, Eq)]
-enum Parsed {
+pub enum Parsed<'a> {
     /// Flags must be valid unicode. Undecodable units are only
     /// allowed after the = of a long parameter, and in non-flags.
-    Invalid(OsString),
+    Invalid(Cow<'a, [u8]>),
 
     /// Fully decodable argument starting with a dash.
-    Short { flags: String },
+    Short { flags: Cow<'a, str> },
 
     /// Partially decodable argument starting with a dash. The tail contains
     /// anything from the first undecodable code unit on.
-    ShortTail { flags: String, tail: OsString },
+    ShortTail {
+        flags: Cow<'a, str>,
+        tail: Cow<'a, [u8]>,
+    },
 
     /// Argument starting with a double dash, possibly with a
-    /// a parameter delimited with an equals sign.
+    /// a parameter delimited with an equals sign or another separator, see
+    /// [`Separators`]. The separator is `None` if the parameter directly
+    /// follows the flag. Also a prefix option with a single dash, such as
+    /// `-Wall` for prefix `-W`.
     Long {
-        flag: String,
-        parameter: Option<OsString>,
+        flag: Cow<'a, str>,
+        separator: Option<char>,
+        parameter: Option<Cow<'a, [u8]>>,
     },
 
     /// Anything that does not start with a dash, or the special cases
     /// `-` and `--`.
-    Arg(OsString),
+    Arg(Cow<'a, [u8]>),
 }
 
-impl Parsed {
-    fn new(s: impl AsRef<OsStr>) -> Self {
-        let (head, tail) = crate::oschars::split_valid(s.as_ref());
+/// Split into the longest prefix that is valid UTF-8 and the rest.
+fn split_valid(bytes: &[u8]) -> (&str, &[u8]) {
+    let valid_to = match str::from_utf8(bytes) {
+        Ok(s) => s.len(),
+        Err(e) => e.valid_up_to(),
+    };
+    let valid_head = unsafe {
+        // SAFETY: valid_to was derived from str::from_utf8.
+        str::from_utf8_unchecked(&bytes[..valid_to])
+    };
+    (valid_head, &bytes[valid_to..])
+}
+
+impl<'a> Parsed<'a> {
+    pub fn new(s: &'a [u8]) -> Self {
+        Parsed::with_separators(s, &Separators::default())
+    }
+
+    pub fn with_separators(s: &'a [u8], separators: &Separators) -> Self {
+        let (head, tail) = split_valid(s);
         if (head == "--" || head == "-") && tail.is_empty() {
-            Parsed::Arg(OsString::from(head))
+            Parsed::Arg(Cow::Borrowed(s))
         } else if head.starts_with("--") {
-            Parsed::parse_long(head, tail)
+            Parsed::parse_long(s, head, tail, separators)
         } else if head.starts_with('-') {
-            if tail.is_empty() {
+            if let Some((idx, separator)) = separators.split_flag(head, !tail.is_empty()) {
+                Parsed::new_attached(s, idx, separator)
+            } else if tail.is_empty() {
                 Parsed::new_short(head)
             } else {
                 Parsed::new_short_tail(head, tail)
             }
         } else {
-            Parsed::Arg(OsString::from(s.as_ref()))
+            Parsed::Arg(Cow::Borrowed(s))
         }
     }
 
-    fn new_short(flags: String) -> Self {
+    fn new_short(flags: &'a str) -> Self {
         assert!(flags.len() > 1);
-        Parsed::Short { flags }
+        Parsed::Short {
+            flags: Cow::Borrowed(flags),
+        }
     }
 
-    fn new_short_tail(flags: String, tail: OsString) -> Self {
+    fn new_short_tail(flags: &'a str, tail: &'a [u8]) -> Self {
         assert!(!tail.is_empty());
-        Parsed::ShortTail { flags, tail }
+        Parsed::ShortTail {
+            flags: Cow::Borrowed(flags),
+            tail: Cow::Borrowed(tail),
+        }
     }
 
-    fn parse_long(head: String, tail: OsString) -> Self {
+    fn parse_long(s: &'a [u8], head: &'a str, tail: &'a [u8], separators: &Separators) -> Self {
         assert!(head.starts_with("--"));
-        let flag;
-        let parameter;
-        if let Some(idx) = head.find('=') {
-            flag = head[..idx].to_string();
-            let mut param = OsString::from(&head[idx + 1..]);
-            param.push(tail);
-            parameter = Some(param);
+        if let Some((idx, separator)) = separators.split(head, !tail.is_empty()) {
+            Parsed::new_attached(s, idx, separator)
         } else if head != "--" && tail.is_empty() {
-            flag = head[..].to_string();
-            parameter = None;
+            Parsed::Long {
+                flag: Cow::Borrowed(head),
+                separator: None,
+                parameter: None,
+            }
         } else {
             // flag must be all-valid unicode
-            let mut s = OsString::from(head);
-            s.push(tail);
-            return Parsed::Invalid(s);
+            Parsed::Invalid(Cow::Borrowed(s))
+        }
+    }
+
+    /// A flag that ends at `idx`, followed by `separator` and a parameter.
+    fn new_attached(s: &'a [u8], idx: usize, separator: Option<char>) -> Self {
+        let flag = str::from_utf8(&s[..idx]).expect("flag is valid unicode");
+        Parsed::Long {
+            flag: Cow::Borrowed(flag),
+            separator,
+            parameter: Some(Cow::Borrowed(
+                &s[idx + separator.map_or(0, char::len_utf8)..],
+            )),
+        }
+    }
+
+    /// Copy the text so it no longer borrows from the original argument.
+    pub fn into_owned(self) -> Parsed<'static> {
+        fn own<T: ToOwned + ?Sized>(cow: Cow<'_, T>) -> Cow<'static, T> {
+            Cow::Owned(cow.into_owned())
+        }
+        match self {
+            Parsed::Invalid(s) => Parsed::Invalid(own(s)),
+            Parsed::Short { flags } => Parsed::Short { flags: own(flags) },
+            Parsed::ShortTail { flags, tail } => Parsed::ShortTail {
+                flags: own(flags),
+                tail: own(tail),
+            },
+            Parsed::Long {
+                flag,
+                separator,
+                parameter,
+            } => Parsed::Long {
+                flag: own(flag),
+                separator,
+                parameter: parameter.map(own),
+            },
+            Parsed::Arg(s) => Parsed::Arg(own(s)),
+        }
+    }
+
+    /// Reconstruct the argument as it was originally written.
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Parsed::Invalid(s) | Parsed::Arg(s) => s.to_vec(),
+            Parsed::Short { flags } => flags.as_bytes().to_vec(),
+            Parsed::ShortTail { flags, tail } => [flags.as_bytes(), tail].concat(),
+            Parsed::Long {
+                flag,
+                parameter: None,
+                ..
+            } => flag.as_bytes().to_vec(),
+            Parsed::Long {
+                flag,
+                separator,
+                parameter: Some(parameter),
+            } => join_long(flag.as_bytes(), *separator, parameter),
         }
-        Parsed::Long { flag, parameter }
     }
 }
 
 #[test]
 fn test_parsed() {
-    use crate::oschars::bad_text as bad;
-    let oss = |s: &str| OsString::from(s);
+    let arg = |s: &[u8]| Parsed::Arg(s.to_vec().into());
+    let long = |flag: &str, parameter: Option<&[u8]>| Parsed::Long {
+        flag: flag.to_string().into(),
+        separator: parameter.map(|_| '='),
+        parameter: parameter.map(|p| p.to_vec().into()),
+    };
+    let short_tail = |flags: &str, tail: &[u8]| Parsed::ShortTail {
+        flags: flags.to_string().into(),
+        tail: tail.to_vec().into(),
+    };
 
-    assert_eq!(Parsed::new(oss("banana")), Parsed::Arg(oss("banana")));
-    assert_eq!(
-        Parsed::new(oss("--follow")),
-        Parsed::Long {
-            flag: "--follow".to_string(),
-            parameter: None
-        }
-    );
+    assert_eq!(Parsed::new(b"banana"), arg(b"banana"));
+    assert_eq!(Parsed::new(b"--follow"), long("--follow", None));
     assert_eq!(
-        Parsed::new(oss("--fruit=banana")),
-        Parsed::Long {
-            flag: "--fruit".to_string(),
-            parameter: Some(oss("banana"))
-        }
+        Parsed::new(b"--fruit=banana"),
+        long("--fruit", Some(b"banana"))
     );
     assert_eq!(
-        Parsed::new(oss("-fv")),
+        Parsed::new(b"-fv"),
         Parsed::Short {
-            flags: "-fv".to_string(),
+            flags: "-fv".into(),
         }
     );
 
-    assert_eq!(Parsed::new(oss("")), Parsed::Arg(oss("")));
-    assert_eq!(Parsed::new(oss("-")), Parsed::Arg(oss("-")));
-    assert_eq!(Parsed::new(oss("--")), Parsed::Arg(oss("--")));
+    assert_eq!(Parsed::new(b""), arg(b""));
+    assert_eq!(Parsed::new(b"-"), arg(b"-"));
+    assert_eq!(Parsed::new(b"--"), arg(b"--"));
+    assert_eq!(Parsed::new(b"---"), long("---", None));
+
+    assert_eq!(Parsed::new(b"banana\xFF"), arg(b"banana\xFF"));
+    assert_eq!(Parsed::new(b"\xFF"), arg(b"\xFF"));
+    assert_eq!(Parsed::new(b"-f\xFF"), short_tail("-f", b"\xFF"));
     assert_eq!(
-        Parsed::new(oss("---")),
-        Parsed::Long {
-            flag: "---".to_string(),
-            parameter: None
-        }
+        Parsed::new(b"--fruit=bana\xFF"),
+        long("--fruit", Some(b"bana\xFF"))
     );
 
-    assert_eq!(Parsed::new(bad("banana")), Parsed::Arg(bad("banana")));
-    assert_eq!(Parsed::new(bad("")), Parsed::Arg(bad("")));
+    assert_eq!(Parsed::new(b"-\xFF"), short_tail("-", b"\xFF"));
     assert_eq!(
-        Parsed::new(bad("-f")),
-        Parsed::ShortTail {
-            flags: "-f".to_string(),
-            tail: bad("")
-        }
+        Parsed::new(b"--\xFF"),
+        Parsed::Invalid(b"--\xFF".to_vec().into())
     );
     assert_eq!(
-        Parsed::new(bad("--fruit=bana")),
-        Parsed::Long {
-            flag: "--fruit".to_string(),
-            parameter: Some(bad("bana"))
-        }
+        Parsed::new(b"--flag\xFF"),
+        Parsed::Invalid(b"--flag\xFF".to_vec().into())
     );
 
-    assert_eq!(
-        Parsed::new(bad("-")),
-        Parsed::ShortTail {
-            flags: "-".to_string(),
-            tail: bad("")
+    for text in [
+        &b"banana"[..],
+        b"-fv",
+        b"--fruit=banana",
+        b"--fruit=ban=ana",
+        b"---",
+        b"-f\xFF",
+        b"--fruit=bana\xFF",
+        b"--flag\xFF",
+    ] {
+        let parsed = Parsed::new(text);
+        assert_eq!(parsed.to_bytes(), text);
+        assert_eq!(parsed.into_owned().to_bytes(), text);
+    }
+}
+
+/// Which characters separate a long flag from its parameter, such as the
+/// `=` in `--fruit=banana`. Flags can have their own separators, which take
+/// precedence. If a flag's list is empty, its parameter directly follows
+/// the name, as in `--DNAME` for flag `--D`. Such prefixes can also have a
+/// single dash, as in `-Wall` for flag `-W`. The default separators only
+/// apply to flags with a double dash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Separators {
+    default: Cow<'static, [char]>,
+    flags: Vec<(String, Vec<char>)>,
+}
+
+impl Default for Separators {
+    fn default() -> Self {
+        Separators {
+            default: Cow::Borrowed(&['=']),
+            flags: Vec::new(),
         }
-    );
-    assert_eq!(Parsed::new(bad("--")), Parsed::Invalid(bad("--")));
-    assert_eq!(Parsed::new(bad("--flag")), Parsed::Invalid(bad("--flag")));
+    }
+}
+
+impl Separators {
+    /// Set the separators of flags that do not have their own.
+    pub fn set_default(&mut self, separators: &[char]) {
+        self.default = Cow::Owned(separators.to_vec());
+    }
+
+    /// Give `flag` its own separators, replacing any it already had.
+    pub fn set_for_flag(&mut self, flag: &str, separators: &[char]) {
+        self.flags.retain(|(f, _)| f != flag);
+        self.flags.push((flag.to_string(), separators.to_vec()));
+        // longest first, so the most specific flag wins
+        self.flags.sort_by_key(|(f, _)| core::cmp::Reverse(f.len()));
+    }
+
+    /// Where the flag in `head`, the valid part of a long flag, ends and
+    /// which separator follows it, if any. `has_tail` tells whether
+    /// anything that is not valid Unicode follows `head`.
+    fn split(&self, head: &str, has_tail: bool) -> Option<(usize, Option<char>)> {
+        self.split_flag(head, has_tail).or_else(|| {
+            head[2..]
+                .char_indices()
+                .find(|(_, c)| self.default.contains(c))
+                .map(|(idx, c)| (2 + idx, Some(c)))
+        })
+    }
+
+    /// Like `split` but only for flags that have their own separators. This
+    /// is all there is for flags with a single dash.
+    pub(crate) fn split_flag(&self, head: &str, has_tail: bool) -> Option<(usize, Option<char>)> {
+        let long = head.starts_with("--");
+        for (flag, separators) in &self.flags {
+            if flag.starts_with("--") != long {
+                continue;
+            }
+            let rest = match head.strip_prefix(flag.as_str()) {
+                Some(rest) => rest,
+                None => continue,
+            };
+            if separators.is_empty() {
+                if !rest.is_empty() || has_tail {
+                    return Some((flag.len(), None));
+                }
+            } else if let Some(c) = rest.chars().next().filter(|c| separators.contains(c)) {
+                return Some((flag.len(), Some(c)));
+            }
+        }
+        None
+    }
+
+    /// Like `split` but for a long flag that is not valid Unicode, which
+    /// can only have one of the default separators.
+    fn split_bytes(&self, s: &[u8]) -> Option<(usize, char)> {
+        let mut buf = [0; 4];
+        (2..s.len()).find_map(|idx| {
+            self.default
+                .iter()
+                .find(|c| s[idx..].starts_with(c.encode_utf8(&mut buf).as_bytes()))
+                .map(|&c| (idx, c))
+        })
+    }
+}
+
+/// The text of a short flag split off from a combi, such as the `-v` out of
+/// `-vx`. A single character is stored inline so splitting a combi does not
+/// allocate. Only grapheme clusters, see [`CoreWalker::split_graphemes`],
+/// can be too long for that.
+#[derive(Debug, Clone)]
+enum ShortFlag {
+    Inline { buf: [u8; 5], len: u8 },
+    Heap(String),
+}
+
+impl ShortFlag {
+    fn new(text: &str) -> Self {
+        let mut buf = [0; 5];
+        if text.len() >= buf.len() {
+            return ShortFlag::Heap(["-", text].concat());
+        }
+        buf[0] = b'-';
+        buf[1..=text.len()].copy_from_slice(text.as_bytes());
+        ShortFlag::Inline {
+            buf,
+            len: 1 + text.len() as u8,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            ShortFlag::Inline { buf, len } => {
+                str::from_utf8(&buf[..*len as usize]).expect("built from a str")
+            }
+            ShortFlag::Heap(flag) => flag,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
-enum State {
+enum FlagText<'a> {
+    Long(Cow<'a, str>),
+    Short(ShortFlag),
+    /// Only when invalid flags are allowed, see
+    /// [`CoreWalker::allow_invalid_flags`].
+    Invalid(Cow<'a, [u8]>),
+}
+
+impl FlagText<'_> {
+    fn as_item(&self) -> ByteItem<'_> {
+        match self {
+            FlagText::Long(flag) => ByteItem::Flag(flag),
+            FlagText::Short(flag) => ByteItem::Flag(flag.as_str()),
+            FlagText::Invalid(flag) => ByteItem::FlagBytes(flag),
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            FlagText::Long(flag) => flag.as_bytes(),
+            FlagText::Short(flag) => flag.as_str().as_bytes(),
+            FlagText::Invalid(flag) => flag,
+        }
+    }
+
+    /// For error messages, which hold a `String`.
+    fn to_lossy(&self) -> String {
+        String::from_utf8_lossy(self.as_bytes()).into_owned()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum State<'a> {
     /// The previously returned item, if any, was not a flag. Maybe we are at
     /// the start, or we have just returned a word.
     NoFlag {
-        word: OsString,
+        word: Cow<'a, [u8]>,
     },
 
     /// The previously returned item was a flag, either something like
     /// `--verbose` or the last letter of a short combi such as `-x` out of
-    /// `-vx`. It has already been removed from our Vec<Parsed> but we need to
+    /// `-vx`. It has already been removed from our queue but we need to
     /// hold on to the text because we returned a reference to it. There was
     /// nothing that could possibly be regarded as a parameter for this flag.
     Flag {
-        flag: String,
+        flag: FlagText<'a>,
     },
 
     /// The previously returned item was a long flag with a parameter, something
-    /// like `--fruit=banana`. The item has been removed from our Vec<Parsed>
+    /// like `--fruit=banana`. The item has been removed from our queue
     /// but we hold on to the text because we returned a reference to it, and
     /// for error messages. We also hold on to the parameter because caller should
     /// ask for it soon. Boolean `taken` is used to keep track of whether this has
     /// happened yet.
     ParmFlag {
-        flag: String,
-        parameter: OsString,
+        flag: FlagText<'a>,
+        separator: Option<char>,
+        parameter: Cow<'a, [u8]>,
         taken: bool,
     },
 
     /// The previously returned item was a short flag that came out of a
-    /// short combi. For example, the `-v` out of `-vx`. The remainder of the combi
-    /// is still in our Vec<Parsed>, including the leading dash. In the example
-    /// above this means that the Vec<Parsed> now starts with `-v`. If the caller
-    /// asks for a parameter, we will return the `v`.
+    /// short combi. For example, the `-v` out of `-vxy`. The combi has been
+    /// removed from our queue and is kept here, `offset` points at the
+    /// first letter that has not been handed out yet. In the example above
+    /// that is the `x`. If the caller asks for a parameter, we will return
+    /// the `xy`.
     SplitFlag {
-        flag: String,
+        flag: ShortFlag,
+        combi: Parsed<'a>,
+        offset: usize,
         taken: bool,
     },
 
     /// The previously returned item was an error.
-    ErrorSeen(ArgError),
+    ErrorSeen(ByteError),
+
+    /// The previously returned item was an error in the middle of a short
+    /// combi, such as the lone combining mark in `-\u{301}v`. Like
+    /// `SplitFlag`, the rest of the combi from `offset` on is handed out
+    /// next.
+    SplitError {
+        err: ByteError,
+        combi: Parsed<'a>,
+        offset: usize,
+    },
 
     EndSeen,
 
     Initial,
 }
 
-impl State {
-    fn as_item(&self) -> ArgResult<Option<ItemOs>> {
-        use ItemOs::*;
-        let flag = match self {
-            State::NoFlag { word } => return Ok(Some(Word(word))),
-            State::Flag { flag } => flag,
-            State::ParmFlag { flag, .. } => flag,
-            State::SplitFlag { flag, .. } => flag,
-            State::ErrorSeen(err) => return Err(err.clone()),
+impl<'a> State<'a> {
+    fn as_item(&self) -> ByteResult<Option<ByteItem<'_>>> {
+        use ByteItem::*;
+        let item = match self {
+            State::NoFlag { word } => Word(word),
+            State::Flag { flag } => flag.as_item(),
+            State::ParmFlag { flag, .. } => flag.as_item(),
+            State::SplitFlag { flag, .. } => Flag(flag.as_str()),
+            State::ErrorSeen(err) | State::SplitError { err, .. } => return Err(err.clone()),
             State::EndSeen => return Ok(None),
             State::Initial => panic!("as_item should never get invoked while in state Initial"),
         };
-        Ok(Some(ItemOs::Flag(flag)))
+        Ok(Some(item))
+    }
+
+    /// Take the next flag out of a short combi, starting at `offset`.
+    fn split_combi(combi: Parsed<'a>, offset: usize, config: &Config) -> State<'a> {
+        let (flags, tail) = match &combi {
+            Parsed::Short { flags } => (flags, None),
+            Parsed::ShortTail { flags, tail } => (flags, Some(tail)),
+            _ => panic!("split_combi called on something that is not a short combi"),
+        };
+        let text = next_short(&flags[offset..], config.graphemes);
+        let text = match text {
+            Ok("") => {
+                // only the undecodable tail is left
+                let flag = [b"-", &tail.expect("combi is not exhausted")[..]].concat();
+                return if config.invalid_flags {
+                    State::Flag {
+                        flag: FlagText::Invalid(Cow::Owned(flag)),
+                    }
+                } else {
+                    State::ErrorSeen(ByteError::InvalidUnicode(flag))
+                };
+            }
+            Ok(text) => text,
+            Err(err) => {
+                // skip the offending cluster but keep the rest of the combi
+                let offset = offset + grapheme::first_cluster_len(&flags[offset..]);
+                return if offset == flags.len() && tail.is_none() {
+                    State::ErrorSeen(err)
+                } else {
+                    State::SplitError { err, combi, offset }
+                };
+            }
+        };
+        let flag = ShortFlag::new(text);
+        let offset = offset + text.len();
+        if offset == flags.len() && tail.is_none() {
+            State::Flag {
+                flag: FlagText::Short(flag),
+            }
+        } else {
+            State::SplitFlag {
+                flag,
+                combi,
+                offset,
+                taken: false,
+            }
+        }
     }
 }
 
+/// What [`CoreWalker::advance`] will return next. Words and long flags are
+/// not copied, they are read from the front of the queue.
 #[derive(Debug, Clone)]
-pub struct CoreWalker {
-    state: State,
-    args: Vec<Parsed>,
-    preview_state: State,
+enum Preview {
+    Front,
+    Short(ShortFlag),
+    /// The undecodable tail of a combi, when invalid flags are allowed.
+    InvalidShort(Vec<u8>),
+    Error(ByteError),
+    End,
+}
+
+/// Arguments that have not been read yet, see [`CoreWalker::from_source`].
+struct Source<'a>(Box<dyn Iterator<Item = Vec<u8>> + Send + 'a>);
+
+// SAFETY: the iterator is only ever used through `&mut self`, in `fill` and
+// `take_rest`, so sharing a `&Source` between threads gives no access to it.
+unsafe impl Sync for Source<'_> {}
+
+impl fmt::Debug for Source<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Source(..)")
+    }
+}
+
+#[derive(Debug)]
+pub struct CoreWalker<'a> {
+    state: State<'a>,
+    /// Arguments that have been read but not handed out yet. When reading
+    /// from a source this holds at most one argument, the one needed for
+    /// the preview.
+    args: VecDeque<Parsed<'a>>,
+    source: Option<Source<'a>>,
+    preview: Preview,
+    config: Config,
+}
+
+/// Settings that affect how arguments are split.
+#[derive(Debug, Clone, Default)]
+struct Config {
+    /// See [`CoreWalker::allow_invalid_flags`].
+    invalid_flags: bool,
+    /// See [`CoreWalker::split_graphemes`].
+    graphemes: bool,
+    /// See [`CoreWalker::reject_look_alikes`].
+    look_alikes: bool,
+    /// See [`CoreWalker::strict_flags`].
+    strict: bool,
+    /// See [`CoreWalker::allow_short_equals`].
+    short_equals: bool,
+    /// See [`CoreWalker::long_separators`].
+    separators: Separators,
+}
+
+impl Config {
+    /// The error for an argument that is rejected as a whole by one of the
+    /// checks that have been enabled.
+    fn reject(&self, arg: &Parsed<'_>) -> Option<ByteError> {
+        match arg {
+            Parsed::Arg(word) if self.look_alikes => look_alike(word),
+            Parsed::Long {
+                flag, parameter, ..
+            } if self.strict && flag.starts_with("--") => {
+                Self::malformed(arg, &flag[2..], parameter.is_some())
+            }
+            Parsed::Invalid(s) if self.strict && self.invalid_flags => {
+                let (flag, has_parameter) = match self.separators.split_bytes(s) {
+                    Some((idx, _)) => (&s[..idx], true),
+                    None => (&s[..], false),
+                };
+                // only the valid part can be checked
+                let (head, _) = split_valid(flag);
+                Self::malformed(arg, &head[2..], has_parameter)
+            }
+            _ => None,
+        }
+    }
+
+    fn malformed(arg: &Parsed<'_>, name: &str, has_parameter: bool) -> Option<ByteError> {
+        let malformed = name.starts_with('-')
+            || (name.is_empty() && has_parameter)
+            || name.chars().any(|c| c.is_whitespace() || c.is_control());
+        if malformed {
+            let arg = String::from_utf8_lossy(&arg.to_bytes()).into_owned();
+            Some(ByteError::MalformedFlag(arg))
+        } else {
+            None
+        }
+    }
 }
 
-impl CoreWalker {
+// Only needed by the tests, a source cannot be cloned.
+#[cfg(test)]
+impl Clone for CoreWalker<'_> {
+    fn clone(&self) -> Self {
+        assert!(self.source.is_none(), "cannot clone a walker with a source");
+        CoreWalker {
+            state: self.state.clone(),
+            args: self.args.clone(),
+            source: None,
+            preview: self.preview.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl CoreWalker<'static> {
+    #[cfg(test)]
     pub fn new<S, T>(args: T) -> Self
     where
         T: IntoIterator<Item = S>,
-        S: AsRef<OsStr>,
+        S: AsRef<[u8]>,
     {
-        let args: Vec<Parsed> = args.into_iter().map(Parsed::new).collect();
-        let state = State::Initial;
-        let preview = Self::compute_preview(&State::Initial, args.first());
-        CoreWalker {
-            args,
-            state,
-            preview_state: preview,
-        }
+        Self::from_parsed(
+            args.into_iter()
+                .map(|a| Parsed::new(a.as_ref()).into_owned()),
+        )
     }
+}
 
-    pub fn advance(&mut self) -> ArgResult<Option<ItemOs>> {
-        let mut st = State::Initial;
-        mem::swap(&mut st, &mut self.state);
-        self.state = match st {
-            State::SplitFlag { flag, taken: true } => {
-                assert!(self.args.is_empty().not());
-                self.args.remove(0);
-                State::Flag { flag }
-            }
-            s => s,
+impl<'a> CoreWalker<'a> {
+    /// Walk arguments that outlive the walker without copying them.
+    pub fn borrowed<S: AsRef<[u8]>>(args: &'a [S]) -> Self {
+        Self::from_parsed(args.iter().map(|a| Parsed::new(a.as_ref())))
+    }
+
+    pub fn from_parsed(args: impl IntoIterator<Item = Parsed<'a>>) -> Self {
+        let mut walker = CoreWalker {
+            args: args.into_iter().collect(),
+            state: State::Initial,
+            source: None,
+            preview: Preview::End,
+            config: Config::default(),
         };
+        walker.update_preview();
+        walker
+    }
 
-        let arg = if self.args.is_empty() {
-            None
-        } else {
-            Some(self.args.remove(0))
+    /// Read the arguments from `source` one at a time, as they are needed.
+    pub fn from_source(source: Box<dyn Iterator<Item = Vec<u8>> + Send + 'a>) -> Self {
+        let mut walker = CoreWalker {
+            args: VecDeque::new(),
+            state: State::Initial,
+            source: Some(Source(source)),
+            preview: Preview::End,
+            config: Config::default(),
         };
+        walker.update_preview();
+        walker
+    }
 
-        let Decision {
-            new_state,
-            push_back,
-        } = decide(&self.state, arg);
-        self.state = new_state;
-        if let Some(a) = push_back {
-            self.args.insert(0, a);
+    /// Hand out flags that are not valid Unicode as [`ByteItem::FlagBytes`]
+    /// instead of rejecting them with [`ByteError::InvalidUnicode`].
+    pub fn allow_invalid_flags(&mut self, enabled: bool) {
+        self.config.invalid_flags = enabled;
+        self.update_preview();
+    }
+
+    /// Split combis into grapheme clusters rather than characters, and
+    /// reject flags that are a lone combining mark.
+    pub fn split_graphemes(&mut self, enabled: bool) {
+        self.config.graphemes = enabled;
+        self.update_preview();
+    }
+
+    /// Reject words that start with a look-alike of a dash or a quote, see
+    /// [`look_alike`].
+    pub fn reject_look_alikes(&mut self, enabled: bool) {
+        self.config.look_alikes = enabled;
+        self.update_preview();
+    }
+
+    /// Reject long flags that make no sense with [`ByteError::MalformedFlag`]:
+    /// `---x`, `--=x` and names with whitespace or control characters.
+    pub fn strict_flags(&mut self, enabled: bool) {
+        self.config.strict = enabled;
+        self.update_preview();
+    }
+
+    /// Leave out the `=` from the parameter of `-f=banana`.
+    pub fn allow_short_equals(&mut self, enabled: bool) {
+        self.config.short_equals = enabled;
+    }
+
+    /// Set the characters that separate a long flag from its parameter,
+    /// see [`Separators`].
+    pub fn long_separators(&mut self, separators: &[char]) {
+        self.config.separators.set_default(separators);
+        self.resplit();
+    }
+
+    /// Set the separators of a single long flag, see [`Separators`].
+    pub fn flag_separators(&mut self, flag: &str, separators: &[char]) {
+        self.config.separators.set_for_flag(flag, separators);
+        self.resplit();
+    }
+
+    /// Declare a prefix option such as `-W`, so that `-Wall` is the flag
+    /// `-W` with parameter `all` rather than a short combi. The longest
+    /// declared prefix wins.
+    pub fn prefix_option(&mut self, prefix: &str) {
+        assert!(
+            prefix.starts_with('-') && prefix.len() > 1 && prefix != "--",
+            "invalid prefix option {:?}",
+            prefix
+        );
+        self.flag_separators(prefix, &[]);
+    }
+
+    /// Split the flags that have been read again, after the separators
+    /// have changed. Flags with a parameter and short combis with an
+    /// undecodable tail are copied.
+    fn resplit(&mut self) {
+        let separators = &self.config.separators;
+        for arg in self.args.iter_mut() {
+            let original = match arg {
+                Parsed::Arg(_) => continue,
+                Parsed::Invalid(s) => s.clone(),
+                Parsed::Short {
+                    flags: Cow::Borrowed(flags),
+                }
+                | Parsed::Long {
+                    flag: Cow::Borrowed(flags),
+                    parameter: None,
+                    ..
+                } => Cow::Borrowed(flags.as_bytes()),
+                _ => Cow::Owned(arg.to_bytes()),
+            };
+            *arg = match original {
+                Cow::Borrowed(s) => Parsed::with_separators(s, separators),
+                Cow::Owned(s) => Parsed::with_separators(&s, separators).into_owned(),
+            };
+        }
+        self.update_preview();
+    }
+
+    /// Make sure the next argument, if any, has been read from the source.
+    fn fill(&mut self) {
+        if !self.args.is_empty() {
+            return;
+        }
+        if let Some(Source(source)) = &mut self.source {
+            match source.next() {
+                Some(arg) => self
+                    .args
+                    .push_back(Parsed::with_separators(&arg, &self.config.separators).into_owned()),
+                None => self.source = None,
+            }
         }
+    }
 
-        self.preview_state = Self::compute_preview(&self.state, self.args.first());
+    fn update_preview(&mut self) {
+        self.fill();
+        self.preview = self.compute_preview();
+    }
+
+    pub fn advance(&mut self) -> ByteResult<Option<ByteItem<'_>>> {
+        let st = mem::replace(&mut self.state, State::Initial);
+        self.state = match st {
+            // Any pending arguments from --flag must be consumed before moving to
+            // the next argument
+            State::ParmFlag {
+                flag, taken: false, ..
+            } => State::ErrorSeen(ByteError::UnexpectedParameter(flag.to_lossy())),
+            State::SplitFlag {
+                combi,
+                offset,
+                taken: false,
+                ..
+            }
+            | State::SplitError { combi, offset, .. } => {
+                State::split_combi(combi, offset, &self.config)
+            }
+            _ => match self.args.pop_front() {
+                Some(arg) => Self::decide(arg, &self.config),
+                None => State::EndSeen,
+            },
+        };
+
+        self.update_preview();
 
         self.state.as_item()
     }
 
-    pub fn upcoming(&self) -> ArgResult<Option<ItemOs>> {
-        self.preview_state.as_item()
+    /// The item [`advance`][CoreWalker::advance] returned most recently.
+    #[cfg(feature = "std")]
+    pub fn current(&self) -> ByteResult<Option<ByteItem<'_>>> {
+        self.state.as_item()
+    }
+
+    fn decide(arg: Parsed<'a>, config: &Config) -> State<'a> {
+        if let Some(err) = config.reject(&arg) {
+            return State::ErrorSeen(err);
+        }
+        match arg {
+            Parsed::Invalid(s) if config.invalid_flags => match split_long(s, &config.separators) {
+                (flag, None) => State::Flag {
+                    flag: FlagText::Invalid(flag),
+                },
+                (flag, Some((separator, parameter))) => State::ParmFlag {
+                    flag: FlagText::Invalid(flag),
+                    separator: Some(separator),
+                    parameter,
+                    taken: false,
+                },
+            },
+            Parsed::Invalid(s) => State::ErrorSeen(ByteError::InvalidUnicode(s.into_owned())),
+            Parsed::Long {
+                flag,
+                parameter: None,
+                ..
+            } => State::Flag {
+                flag: FlagText::Long(flag),
+            },
+            Parsed::Long {
+                flag,
+                separator,
+                parameter: Some(parameter),
+            } => State::ParmFlag {
+                flag: FlagText::Long(flag),
+                separator,
+                parameter,
+                taken: false,
+            },
+            Parsed::Arg(word) => State::NoFlag { word },
+            combi @ Parsed::Short { .. } | combi @ Parsed::ShortTail { .. } => {
+                State::split_combi(combi, 1, config)
+            }
+        }
+    }
+
+    fn compute_preview(&self) -> Preview {
+        match &self.state {
+            State::ParmFlag {
+                flag, taken: false, ..
+            } => return Preview::Error(ByteError::UnexpectedParameter(flag.to_lossy())),
+            State::SplitFlag {
+                combi,
+                offset,
+                taken: false,
+                ..
+            }
+            | State::SplitError { combi, offset, .. } => return self.preview_combi(combi, *offset),
+            _ => {}
+        }
+        match self.args.front() {
+            None => Preview::End,
+            Some(Parsed::Invalid(s)) if !self.config.invalid_flags => {
+                Preview::Error(ByteError::InvalidUnicode(s.to_vec()))
+            }
+            Some(arg @ Parsed::Arg(_))
+            | Some(arg @ Parsed::Long { .. })
+            | Some(arg @ Parsed::Invalid(_)) => match self.config.reject(arg) {
+                Some(err) => Preview::Error(err),
+                None => Preview::Front,
+            },
+            Some(combi) => self.preview_combi(combi, 1),
+        }
     }
 
-    fn compute_preview(state: &State, first: Option<&Parsed>) -> State {
-        let Decision { new_state, .. } = decide(&state, first.cloned());
-        new_state
+    fn preview_combi(&self, combi: &Parsed<'_>, offset: usize) -> Preview {
+        match combi {
+            Parsed::Short { flags } | Parsed::ShortTail { flags, .. } if offset < flags.len() => {
+                match next_short(&flags[offset..], self.config.graphemes) {
+                    Ok(text) => Preview::Short(ShortFlag::new(text)),
+                    Err(err) => Preview::Error(err),
+                }
+            }
+            Parsed::ShortTail { tail, .. } => {
+                let flag = [b"-", &tail[..]].concat();
+                if self.config.invalid_flags {
+                    Preview::InvalidShort(flag)
+                } else {
+                    Preview::Error(ByteError::InvalidUnicode(flag))
+                }
+            }
+            _ => panic!("preview_combi called on something that is not a short combi"),
+        }
+    }
+
+    pub fn upcoming(&self) -> ByteResult<Option<ByteItem<'_>>> {
+        match &self.preview {
+            Preview::Front => match self.args.front() {
+                Some(Parsed::Arg(word)) => Ok(Some(ByteItem::Word(word))),
+                Some(Parsed::Long { flag, .. }) => Ok(Some(ByteItem::Flag(flag))),
+                Some(Parsed::Invalid(s)) => {
+                    let flag = match self.config.separators.split_bytes(s) {
+                        Some((idx, _)) => &s[..idx],
+                        None => s,
+                    };
+                    Ok(Some(ByteItem::FlagBytes(flag)))
+                }
+                _ => panic!("preview is Front but front is not a word or long flag"),
+            },
+            Preview::Short(flag) => Ok(Some(ByteItem::Flag(flag.as_str()))),
+            Preview::InvalidShort(flag) => Ok(Some(ByteItem::FlagBytes(flag))),
+            Preview::Error(err) => Err(err.clone()),
+            Preview::End => Ok(None),
+        }
     }
 
-    pub fn current_flag(&self) -> Option<&str> {
+    /// The current flag, for error messages.
+    pub fn current_flag(&self) -> Option<String> {
         match &self.state {
             State::NoFlag { .. } => None,
-            State::Flag { flag } => Some(&flag),
-            State::ParmFlag { flag, .. } => Some(&flag),
-            State::SplitFlag { flag, .. } => Some(&flag),
-            State::ErrorSeen(_) => None,
+            State::Flag { flag } => Some(flag.to_lossy()),
+            State::ParmFlag { flag, .. } => Some(flag.to_lossy()),
+            State::SplitFlag { flag, .. } => Some(flag.as_str().to_string()),
+            State::ErrorSeen(_) | State::SplitError { .. } => None,
             State::EndSeen => None,
             State::Initial => None,
         }
@@ -296,164 +936,247 @@ impl CoreWalker {
         )
     }
 
-    pub fn parameter(&mut self) -> Option<&OsStr> {
-        let mut shift_preview = false;
-        let parm = match &mut self.state {
+    /// The arguments that have been read but not handed out yet, as they
+    /// were written, or an error if the parameter of the current long flag
+    /// has not been taken.
+    fn unconsumed(&self) -> ByteResult<Vec<Vec<u8>>> {
+        let mut leftover = Vec::new();
+        match &self.state {
             State::ParmFlag {
-                parameter, taken, ..
-            } => {
-                *taken = true;
-                Some(parameter.as_os_str())
+                flag, taken: false, ..
+            } => return Err(ByteError::UnexpectedParameter(flag.to_lossy())),
+            State::SplitFlag {
+                combi,
+                offset,
+                taken: false,
+                ..
             }
-            State::SplitFlag { ref mut taken, .. } => {
-                assert!(self.args.is_empty().not());
-                let parm = match &self.args[0] {
-                    Parsed::Short { flags } | Parsed::ShortTail { flags, .. } => {
-                        *taken = true;
-                        shift_preview = true;
-                        &flags[1..]
-                    }
-                    _ => panic!("am in state SplitFlag without a Short item as args[0]"),
-                };
-                Some(OsStr::new(parm))
+            | State::SplitError { combi, offset, .. } => {
+                leftover.push([b"-", &combi_rest(combi, *offset)[..]].concat());
             }
-            _ => None,
-        };
-
-        if shift_preview {
-            self.preview_state = Self::compute_preview(&State::Initial, self.args.get(1));
+            _ => {}
         }
+        leftover.extend(self.args.iter().map(Parsed::to_bytes));
+        Ok(leftover)
+    }
 
-        parm
+    /// Returns an error if there is anything left that has not been handed
+    /// out yet. This is either a parameter of a long flag that has not been
+    /// taken, or one or more arguments that have not been reached. Arguments
+    /// still in the source are not read, so only the first of those is
+    /// reported.
+    pub fn check_finished(&self) -> ByteResult<()> {
+        let leftover = self.unconsumed()?;
+        if leftover.is_empty() {
+            Ok(())
+        } else {
+            Err(ByteError::UnconsumedArguments(leftover))
+        }
     }
-}
 
-struct Decision {
-    new_state: State,
-    push_back: Option<Parsed>,
-}
+    /// Remove all arguments that have not been handed out yet and return them
+    /// as they were written. Afterwards, we are at the end.
+    pub fn take_rest(&mut self) -> ByteResult<Vec<Vec<u8>>> {
+        let mut rest = self.unconsumed()?;
+        if let Some(Source(source)) = self.source.take() {
+            rest.extend(source);
+        }
+        self.args.clear();
+        self.state = State::EndSeen;
+        self.preview = Preview::End;
+        Ok(rest)
+    }
 
-fn decide(state: &State, arg: Option<Parsed>) -> Decision {
-    use Parsed::*;
-    use State::*;
-
-    match state {
-        // Any pending arguments from --flag must be consumed before moving to
-        // the next argument
-        ParmFlag {
-            flag, taken: false, ..
-        } => {
-            return Decision {
-                new_state: ErrorSeen(ArgError::UnexpectedParameter(flag.clone())),
-                push_back: arg,
-            }
+    /// The current word, if the previously returned item was a word.
+    pub fn current_word(&self) -> Option<Cow<'a, [u8]>> {
+        match &self.state {
+            State::NoFlag { word } => Some(word.clone()),
+            _ => None,
         }
+    }
 
-        // sanity check
-        SplitFlag { taken: true, .. } => {
-            panic!("splitflag taken=true should have been handled before")
+    pub fn parameter(&mut self) -> Option<Cow<'a, [u8]>> {
+        match &mut self.state {
+            State::ParmFlag { taken, .. } | State::SplitFlag { taken, .. } => *taken = true,
+            _ => return None,
         }
 
-        _ => {}
-    }
+        self.update_preview();
 
-    let arg = match arg {
-        Some(a) => a,
-        None => {
-            return Decision {
-                new_state: EndSeen,
-                push_back: None,
+        match &self.state {
+            State::ParmFlag { parameter, .. } => Some(parameter.clone()),
+            State::SplitFlag { combi, offset, .. } => {
+                let equals = match combi {
+                    Parsed::Short { flags } | Parsed::ShortTail { flags, .. } => {
+                        self.config.short_equals && flags[*offset..].starts_with('=')
+                    }
+                    _ => false,
+                };
+                Some(combi_rest(combi, offset + equals as usize))
             }
+            _ => unreachable!(),
         }
-    };
+    }
 
-    match arg {
-        Invalid(s) => Decision {
-            new_state: ErrorSeen(ArgError::InvalidUnicode(s)),
-            push_back: None,
-        },
-
-        Long {
-            flag,
-            parameter: None,
-        } => Decision {
-            new_state: Flag { flag },
-            push_back: None,
-        },
-
-        Long {
-            flag,
-            parameter: Some(parameter),
-        } => Decision {
-            new_state: ParmFlag {
+    /// Reconstruct the text of the current item as it was written, so it can
+    /// be passed on verbatim. For a flag split off from a short combi this
+    /// includes the remainder of the combi, and for a long flag it includes
+    /// its parameter. Both count as taken.
+    ///
+    /// Also works on arguments rejected with [`ByteError::InvalidUnicode`].
+    /// Those are moved out, there is no current item afterwards.
+    pub fn take_original(&mut self) -> Option<Vec<u8>> {
+        let text = match &self.state {
+            State::NoFlag { word } => return Some(word.to_vec()),
+            State::ErrorSeen(ByteError::InvalidUnicode(_)) => {
+                return match mem::replace(&mut self.state, State::EndSeen) {
+                    State::ErrorSeen(ByteError::InvalidUnicode(s)) => Some(s),
+                    _ => unreachable!(),
+                };
+            }
+            State::Flag { flag } => return Some(flag.as_bytes().to_vec()),
+            State::ParmFlag {
                 flag,
+                separator,
                 parameter,
-                taken: false,
-            },
-            push_back: None,
-        },
-
-        Arg(word) => Decision {
-            new_state: NoFlag { word },
-            push_back: None,
-        },
-
-        Short { mut flags } => {
-            let flag = chop_off(&mut flags);
-            if flags == "-" {
-                Decision {
-                    new_state: Flag { flag },
-                    push_back: None,
-                }
-            } else {
-                Decision {
-                    new_state: SplitFlag { flag, taken: false },
-                    push_back: Some(Parsed::new_short(flags)),
-                }
+                ..
+            } => join_long(flag.as_bytes(), *separator, parameter),
+            State::SplitFlag {
+                flag,
+                combi,
+                offset,
+                ..
+            } => [flag.as_str().as_bytes(), &combi_rest(combi, *offset)].concat(),
+            _ => return None,
+        };
+        // mark the parameter as taken
+        self.parameter().expect("state has a parameter");
+        Some(text)
+    }
+}
+
+/// If `word` starts with a look-alike of an ASCII dash or quote, as happens
+/// when a command is copied from a document, return
+/// [`ByteError::LookAlikeDash`] with the ASCII spelling. For example
+/// `\u{2013}verbose`, with an en dash, becomes `--verbose` and
+/// `\u{201C}file\u{201D}` becomes `"file"`.
+pub fn look_alike(word: &[u8]) -> Option<ByteError> {
+    let (head, tail) = split_valid(word);
+    let first = head.chars().next()?;
+    let ascii = ascii_look_alike(first)?;
+    let mut rest = &head[first.len_utf8()..];
+    let mut closing = "";
+    if !ascii.starts_with('-') && tail.is_empty() {
+        if let Some(last) = rest.chars().next_back() {
+            if ascii_look_alike(last) == Some(ascii) {
+                rest = &rest[..rest.len() - last.len_utf8()];
+                closing = ascii;
             }
         }
+    }
+    let suggestion = format!(
+        "{}{}{}{}",
+        ascii,
+        rest,
+        String::from_utf8_lossy(tail),
+        closing
+    );
+    let word = String::from_utf8_lossy(word).into_owned();
+    Some(ByteError::LookAlikeDash(word, suggestion))
+}
 
-        ShortTail { mut flags, tail } => {
-            if flags == "-" {
-                let mut flag = OsString::from("-");
-                flag.push(tail);
-                Decision {
-                    new_state: ErrorSeen(ArgError::InvalidUnicode(flag)),
-                    push_back: None,
-                }
-            } else {
-                let flag = chop_off(&mut flags);
-                Decision {
-                    new_state: SplitFlag { flag, taken: false },
-                    push_back: Some(Parsed::new_short_tail(flags, tail)),
-                }
-            }
+fn ascii_look_alike(c: char) -> Option<&'static str> {
+    let ascii = match c {
+        // en dash and em dash, which word processors make of a double dash
+        '\u{2013}' | '\u{2014}' => "--",
+        // hyphen, non-breaking hyphen, figure dash, minus sign, small and
+        // fullwidth hyphen-minus
+        '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2212}' | '\u{FE63}' | '\u{FF0D}' => "-",
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{AB}' | '\u{BB}' => "\"",
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' => "'",
+        _ => return None,
+    };
+    Some(ascii)
+}
+
+/// The text of the next short flag at the start of `flags`, which is one
+/// character or, with `graphemes`, one grapheme cluster. Returns an empty
+/// string if `flags` is empty.
+fn next_short(flags: &str, graphemes: bool) -> ByteResult<&str> {
+    if !graphemes {
+        let len = flags.chars().next().map_or(0, char::len_utf8);
+        return Ok(&flags[..len]);
+    }
+    let text = &flags[..grapheme::first_cluster_len(flags)];
+    match text.chars().next() {
+        Some(c) if grapheme::is_extend(c) => {
+            Err(ByteError::LoneCombiningMark(["-", text].concat()))
         }
+        _ => Ok(text),
     }
 }
 
-fn chop_off(flags: &mut String) -> String {
-    assert!(flags.starts_with('-'));
-    let ch = flags.remove(1);
-    format!("-{}", ch)
+/// A separator and the parameter that follows it.
+type Attached<'s> = Option<(char, Cow<'s, [u8]>)>;
+
+/// Split a long flag that is not valid Unicode at the first separator.
+fn split_long<'s>(s: Cow<'s, [u8]>, separators: &Separators) -> (Cow<'s, [u8]>, Attached<'s>) {
+    let (idx, separator) = match separators.split_bytes(&s) {
+        Some(found) => found,
+        None => return (s, None),
+    };
+    let start = idx + separator.len_utf8();
+    match s {
+        Cow::Borrowed(s) => (
+            Cow::Borrowed(&s[..idx]),
+            Some((separator, Cow::Borrowed(&s[start..]))),
+        ),
+        Cow::Owned(mut flag) => {
+            let parameter = flag.split_off(start);
+            flag.truncate(idx);
+            (Cow::Owned(flag), Some((separator, Cow::Owned(parameter))))
+        }
+    }
+}
+
+/// Reconstruct a long flag with its parameter.
+fn join_long(flag: &[u8], separator: Option<char>, parameter: &[u8]) -> Vec<u8> {
+    let mut buf = [0; 4];
+    let separator = separator.map_or("", |c| c.encode_utf8(&mut buf));
+    [flag, separator.as_bytes(), parameter].concat()
+}
+
+/// The part of a short combi from `offset` on, without the leading dash.
+fn combi_rest<'a>(combi: &Parsed<'a>, offset: usize) -> Cow<'a, [u8]> {
+    match combi {
+        Parsed::Short {
+            flags: Cow::Borrowed(flags),
+        } => Cow::Borrowed(&flags.as_bytes()[offset..]),
+        Parsed::Short { flags } => Cow::Owned(flags.as_bytes()[offset..].to_vec()),
+        Parsed::ShortTail { flags, tail } => {
+            Cow::Owned([flags[offset..].as_bytes(), tail].concat())
+        }
+        _ => panic!("combi_rest called on something that is not a short combi"),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::ItemOs::*;
+    use super::ByteItem::*;
     use super::*;
+    use std::{format, string::String, vec};
 
     #[test]
     fn test_items() {
-        let mut walker = CoreWalker::new(&["-vx", "-f", "foo"]);
+        let mut walker = CoreWalker::new(["-vx", "-f", "foo"]);
 
         assert_eq!(walker.upcoming(), Ok(Some(Flag("-v"))));
         assert_eq!(walker.advance(), Ok(Some(Flag("-v"))));
 
         // consume the x as a parameter
-        assert_eq!(walker.can_parameter(), true);
+        assert!(walker.can_parameter());
         let mut walker2 = walker.clone();
-        assert_eq!(walker2.parameter(), Some(OsString::from("x").as_os_str()));
+        assert_eq!(walker2.parameter(), Some(Cow::Borrowed(&b"x"[..])));
         assert_eq!(walker2.upcoming(), Ok(Some(Flag("-f"))));
         assert_eq!(walker2.advance(), Ok(Some(Flag("-f"))));
 
@@ -462,7 +1185,7 @@ mod tests {
         assert_eq!(walker.advance(), Ok(Some(Flag("-x"))));
 
         // nothing behind the x
-        assert_eq!(walker.can_parameter(), false);
+        assert!(!walker.can_parameter());
         let mut walker2 = walker.clone();
         assert_eq!(walker2.parameter(), None);
         assert_eq!(walker2.upcoming(), Ok(Some(Flag("-f"))));
@@ -472,24 +1195,15 @@ mod tests {
         assert_eq!(walker.advance(), Ok(Some(Flag("-f"))));
 
         // before attempting to take the (nonexistent) parameter, foo is upcoming
-        assert_eq!(
-            walker.upcoming(),
-            Ok(Some(Word(OsString::from("foo").as_os_str())))
-        );
-        assert_eq!(walker.can_parameter(), false);
+        assert_eq!(walker.upcoming(), Ok(Some(Word(b"foo"))));
+        assert!(!walker.can_parameter());
         assert_eq!(walker.parameter(), None);
 
         // after the attempt, foo is still upcoming
-        assert_eq!(
-            walker.upcoming(),
-            Ok(Some(Word(OsString::from("foo").as_os_str())))
-        );
+        assert_eq!(walker.upcoming(), Ok(Some(Word(b"foo"))));
 
         // after foo we find eof
-        assert_eq!(
-            walker.advance(),
-            Ok(Some(Word(OsString::from("foo").as_os_str())))
-        );
+        assert_eq!(walker.advance(), Ok(Some(Word(b"foo"))));
         assert_eq!(walker.upcoming(), Ok(None));
         assert_eq!(walker.advance(), Ok(None));
 
@@ -503,4 +1217,510 @@ mod tests {
         assert_eq!(walker.upcoming(), Ok(None));
         assert_eq!(walker.advance(), Ok(None));
     }
+
+    #[test]
+    fn test_long_parameter() {
+        let mut walker = CoreWalker::new(["--fruit=banana", "foo"]);
+        assert_eq!(walker.advance(), Ok(Some(Flag("--fruit"))));
+        assert_eq!(
+            walker.upcoming(),
+            Err(ByteError::UnexpectedParameter("--fruit".to_string()))
+        );
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"banana"[..])));
+        assert_eq!(walker.upcoming(), Ok(Some(Word(b"foo"))));
+        assert_eq!(walker.advance(), Ok(Some(Word(b"foo"))));
+    }
+
+    #[test]
+    fn test_take_original() {
+        let mut walker = CoreWalker::new([
+            &b"-vxf"[..],
+            b"--fruit=banana",
+            b"--color",
+            b"-ab\xFF",
+            b"--flag\xFF",
+            b"foo",
+        ]);
+        assert_eq!(walker.take_original(), None);
+        walker.advance().unwrap();
+        walker.advance().unwrap();
+        assert_eq!(walker.take_original(), Some(b"-xf".to_vec()));
+        assert_eq!(walker.advance(), Ok(Some(Flag("--fruit"))));
+        assert_eq!(walker.take_original(), Some(b"--fruit=banana".to_vec()));
+        assert_eq!(walker.advance(), Ok(Some(Flag("--color"))));
+        assert_eq!(walker.take_original(), Some(b"--color".to_vec()));
+        assert_eq!(walker.advance(), Ok(Some(Flag("-a"))));
+        assert_eq!(walker.take_original(), Some(b"-ab\xFF".to_vec()));
+        assert!(walker.advance().is_err());
+        assert_eq!(walker.take_original(), Some(b"--flag\xFF".to_vec()));
+        assert_eq!(walker.take_original(), None);
+        assert_eq!(
+            walker.check_finished(),
+            Err(ByteError::UnconsumedArguments(vec![b"foo".to_vec()]))
+        );
+        walker.advance().unwrap();
+        assert_eq!(walker.take_original(), Some(b"foo".to_vec()));
+        assert_eq!(walker.advance(), Ok(None));
+        assert_eq!(walker.take_original(), None);
+    }
+
+    #[test]
+    fn test_take_rest() {
+        let mut walker = CoreWalker::new(["-vx", "--fruit=banana", "foo"]);
+        walker.advance().unwrap();
+        let mut walker2 = walker.clone();
+        walker2.parameter();
+        assert_eq!(
+            walker2.take_rest(),
+            Ok(vec![b"--fruit=banana".to_vec(), b"foo".to_vec()])
+        );
+        assert_eq!(walker2.upcoming(), Ok(None));
+        assert_eq!(walker2.advance(), Ok(None));
+
+        assert_eq!(
+            walker.take_rest(),
+            Ok(vec![
+                b"-x".to_vec(),
+                b"--fruit=banana".to_vec(),
+                b"foo".to_vec()
+            ])
+        );
+        assert_eq!(walker.advance(), Ok(None));
+        assert_eq!(walker.take_rest(), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_check_finished() {
+        let leftover = |args: &[&str]| {
+            Err(ByteError::UnconsumedArguments(
+                args.iter().map(|a| a.as_bytes().to_vec()).collect(),
+            ))
+        };
+
+        let mut walker = CoreWalker::new(["-vx", "--fruit=banana", "foo"]);
+        assert_eq!(
+            walker.check_finished(),
+            leftover(&["-vx", "--fruit=banana", "foo"])
+        );
+
+        walker.advance().unwrap();
+        assert_eq!(
+            walker.check_finished(),
+            leftover(&["-x", "--fruit=banana", "foo"])
+        );
+        let mut walker2 = walker.clone();
+        walker2.parameter();
+        assert_eq!(
+            walker2.check_finished(),
+            leftover(&["--fruit=banana", "foo"])
+        );
+
+        walker.advance().unwrap();
+        walker.advance().unwrap();
+        assert_eq!(
+            walker.check_finished(),
+            Err(ByteError::UnexpectedParameter("--fruit".to_string()))
+        );
+        walker.parameter();
+        assert_eq!(walker.check_finished(), leftover(&["foo"]));
+
+        walker.advance().unwrap();
+        assert_eq!(walker.check_finished(), Ok(()));
+        walker.advance().unwrap();
+        assert_eq!(walker.check_finished(), Ok(()));
+    }
+
+    #[test]
+    fn test_source() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let read = Arc::new(AtomicUsize::new(0));
+        let counter = read.clone();
+        let source = ["-vx", "--fruit=banana", "foo", "bar"]
+            .iter()
+            .map(move |s| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                s.as_bytes().to_vec()
+            });
+        let mut walker = CoreWalker::from_source(Box::new(source));
+        let read = || read.load(Ordering::SeqCst);
+
+        // one argument is read ahead for the preview
+        assert_eq!(read(), 1);
+        assert_eq!(walker.upcoming(), Ok(Some(Flag("-v"))));
+        assert_eq!(walker.advance(), Ok(Some(Flag("-v"))));
+        assert_eq!(read(), 2);
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"x"[..])));
+        assert_eq!(walker.advance(), Ok(Some(Flag("--fruit"))));
+        assert_eq!(read(), 3);
+        assert_eq!(
+            walker.check_finished(),
+            Err(ByteError::UnexpectedParameter("--fruit".to_string()))
+        );
+        walker.parameter();
+        assert_eq!(
+            walker.check_finished(),
+            Err(ByteError::UnconsumedArguments(vec![b"foo".to_vec()]))
+        );
+        assert_eq!(read(), 3);
+        assert_eq!(
+            walker.take_rest(),
+            Ok(vec![b"foo".to_vec(), b"bar".to_vec()])
+        );
+        assert_eq!(walker.advance(), Ok(None));
+    }
+
+    #[test]
+    fn test_invalid_flags() {
+        let args = [
+            &b"--fl\xFFag"[..],
+            b"--col\xFFr=red",
+            b"-v\xFFx",
+            b"--\xFF=",
+            b"foo",
+        ];
+        let mut walker = CoreWalker::borrowed(&args);
+        assert_eq!(
+            walker.upcoming(),
+            Err(ByteError::InvalidUnicode(b"--fl\xFFag".to_vec()))
+        );
+        walker.allow_invalid_flags(true);
+
+        assert_eq!(walker.upcoming(), Ok(Some(FlagBytes(b"--fl\xFFag"))));
+        assert_eq!(walker.advance(), Ok(Some(FlagBytes(b"--fl\xFFag"))));
+        assert!(!walker.can_parameter());
+
+        assert_eq!(walker.upcoming(), Ok(Some(FlagBytes(b"--col\xFFr"))));
+        assert_eq!(walker.advance(), Ok(Some(FlagBytes(b"--col\xFFr"))));
+        assert_eq!(
+            walker.check_finished(),
+            Err(ByteError::UnexpectedParameter("--col\u{FFFD}r".to_string()))
+        );
+        assert!(matches!(walker.parameter(), Some(Cow::Borrowed(p)) if p == b"red"));
+
+        assert_eq!(walker.advance(), Ok(Some(Flag("-v"))));
+        assert_eq!(walker.upcoming(), Ok(Some(FlagBytes(b"-\xFFx"))));
+        assert_eq!(walker.advance(), Ok(Some(FlagBytes(b"-\xFFx"))));
+        assert_eq!(walker.take_original(), Some(b"-\xFFx".to_vec()));
+
+        assert_eq!(walker.advance(), Ok(Some(FlagBytes(b"--\xFF"))));
+        assert_eq!(walker.take_original(), Some(b"--\xFF=".to_vec()));
+
+        walker.allow_invalid_flags(false);
+        assert_eq!(walker.advance(), Ok(Some(Word(b"foo"))));
+        assert_eq!(walker.advance(), Ok(None));
+    }
+
+    #[test]
+    fn test_graphemes() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let combi = alloc::format!("-ve\u{301}{}x", family);
+        let args = [combi.as_str(), "-\u{301}v", "-fe\u{301}"];
+
+        let mut walker = CoreWalker::borrowed(&args);
+        assert_eq!(walker.advance(), Ok(Some(Flag("-v"))));
+        assert_eq!(walker.advance(), Ok(Some(Flag("-e"))));
+        assert_eq!(walker.advance(), Ok(Some(Flag("-\u{301}"))));
+
+        let mut walker = CoreWalker::borrowed(&args);
+        walker.split_graphemes(true);
+        assert_eq!(walker.advance(), Ok(Some(Flag("-v"))));
+        assert_eq!(walker.upcoming(), Ok(Some(Flag("-e\u{301}"))));
+        assert_eq!(walker.advance(), Ok(Some(Flag("-e\u{301}"))));
+        let flag = alloc::format!("-{}", family);
+        assert_eq!(walker.upcoming(), Ok(Some(Flag(&flag))));
+        assert_eq!(walker.advance(), Ok(Some(Flag(&flag))));
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"x"[..])));
+
+        let lone = ByteError::LoneCombiningMark("-\u{301}".to_string());
+        assert_eq!(walker.upcoming(), Err(lone.clone()));
+        assert_eq!(walker.advance(), Err(lone));
+        // the rest of the combi is not lost
+        assert_eq!(walker.upcoming(), Ok(Some(Flag("-v"))));
+        assert_eq!(walker.advance(), Ok(Some(Flag("-v"))));
+
+        assert_eq!(walker.advance(), Ok(Some(Flag("-f"))));
+        assert_eq!(
+            walker.parameter(),
+            Some(Cow::Borrowed("e\u{301}".as_bytes()))
+        );
+        assert_eq!(walker.advance(), Ok(None));
+    }
+
+    #[test]
+    fn test_look_alikes() {
+        let suggest = |word: &str| match look_alike(word.as_bytes()) {
+            Some(ByteError::LookAlikeDash(w, suggestion)) => {
+                assert_eq!(w, word);
+                Some(suggestion)
+            }
+            other => {
+                assert_eq!(other, None);
+                None
+            }
+        };
+        assert_eq!(suggest("\u{2013}verbose").as_deref(), Some("--verbose"));
+        assert_eq!(
+            suggest("\u{2014}fruit=banana").as_deref(),
+            Some("--fruit=banana")
+        );
+        assert_eq!(suggest("\u{2212}v").as_deref(), Some("-v"));
+        assert_eq!(suggest("\u{201C}file\u{201D}").as_deref(), Some("\"file\""));
+        assert_eq!(suggest("\u{2018}it\u{2019}").as_deref(), Some("'it'"));
+        assert_eq!(suggest("\u{201E}half").as_deref(), Some("\"half"));
+        assert_eq!(suggest("file\u{201D}"), None);
+        assert_eq!(suggest("-v"), None);
+        assert_eq!(suggest(""), None);
+
+        let args = [&b"\xE2\x80\x93v"[..], b"-f", b"\xE2\x80\x9Cfile\xFF"];
+        let mut walker = CoreWalker::borrowed(&args);
+        assert_eq!(walker.advance(), Ok(Some(Word(args[0]))));
+
+        let mut walker = CoreWalker::borrowed(&args);
+        walker.reject_look_alikes(true);
+        let err = ByteError::LookAlikeDash("\u{2013}v".to_string(), "--v".to_string());
+        assert_eq!(walker.upcoming(), Err(err.clone()));
+        assert_eq!(walker.advance(), Err(err));
+        assert_eq!(walker.advance(), Ok(Some(Flag("-f"))));
+        let err = ByteError::LookAlikeDash(
+            "\u{201C}file\u{FFFD}".to_string(),
+            "\"file\u{FFFD}".to_string(),
+        );
+        assert_eq!(walker.upcoming(), Err(err));
+    }
+
+    #[test]
+    fn test_strict_flags() {
+        let args = [
+            "---x",
+            "--=x",
+            "--a b",
+            "--tab\t=x",
+            "--",
+            "--ok=a b",
+            "-f=banana",
+        ];
+        let mut walker = CoreWalker::borrowed(&args);
+        assert_eq!(walker.advance(), Ok(Some(Flag("---x"))));
+        assert_eq!(walker.advance(), Ok(Some(Flag("--"))));
+        walker.parameter();
+
+        let mut walker = CoreWalker::borrowed(&args);
+        walker.strict_flags(true);
+        walker.allow_short_equals(true);
+        for arg in &args[..4] {
+            let err = ByteError::MalformedFlag(arg.to_string());
+            assert_eq!(walker.upcoming(), Err(err.clone()));
+            assert_eq!(walker.advance(), Err(err));
+        }
+        assert_eq!(walker.advance(), Ok(Some(Word(b"--"))));
+        assert_eq!(walker.advance(), Ok(Some(Flag("--ok"))));
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"a b"[..])));
+        assert_eq!(walker.advance(), Ok(Some(Flag("-f"))));
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"banana"[..])));
+        assert_eq!(walker.advance(), Ok(None));
+
+        // flags that are not valid Unicode are checked as far as they are
+        let args = [&b"---x\xFF"[..], b"--=\xFF", b"--a b\xFF", b"--ok\xFF=a b"];
+        let mut walker = CoreWalker::borrowed(&args);
+        walker.strict_flags(true);
+        assert_eq!(
+            walker.advance(),
+            Err(ByteError::InvalidUnicode(b"---x\xFF".to_vec()))
+        );
+        walker.allow_invalid_flags(true);
+        for arg in &args[1..3] {
+            let err = ByteError::MalformedFlag(String::from_utf8_lossy(arg).into_owned());
+            assert_eq!(walker.upcoming(), Err(err.clone()));
+            assert_eq!(walker.advance(), Err(err));
+        }
+        assert_eq!(walker.advance(), Ok(Some(FlagBytes(b"--ok\xFF"))));
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"a b"[..])));
+        assert_eq!(walker.advance(), Ok(None));
+
+        let mut walker = CoreWalker::borrowed(&args);
+        walker.allow_invalid_flags(true);
+        walker.strict_flags(true);
+        let err = ByteError::MalformedFlag("---x\u{FFFD}".to_string());
+        assert_eq!(walker.advance(), Err(err));
+
+        // forwarded as written
+        let mut walker = CoreWalker::borrowed(&["-vf=banana"]);
+        walker.allow_short_equals(true);
+        assert_eq!(walker.advance(), Ok(Some(Flag("-v"))));
+        assert_eq!(walker.take_original(), Some(b"-vf=banana".to_vec()));
+    }
+
+    #[test]
+    fn test_separators() {
+        let args = [
+            &b"--opt:value"[..],
+            b"--opt=value",
+            b"--DNAME",
+            b"--D=x",
+            b"--color:ye\xFF",
+            b"--col\xFFr:red",
+            b"--define:x",
+        ];
+        let mut walker = CoreWalker::borrowed(&args);
+        // already looked at, split again
+        assert_eq!(walker.upcoming(), Ok(Some(Flag("--opt:value"))));
+        walker.long_separators(&[':']);
+        walker.flag_separators("--D", &[]);
+        walker.allow_invalid_flags(true);
+        assert_eq!(walker.upcoming(), Ok(Some(Flag("--opt"))));
+        assert_eq!(walker.advance(), Ok(Some(Flag("--opt"))));
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"value"[..])));
+        assert_eq!(walker.advance(), Ok(Some(Flag("--opt=value"))));
+        assert_eq!(walker.advance(), Ok(Some(Flag("--D"))));
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"NAME"[..])));
+        // the prefix does not have a separator of its own
+        assert_eq!(walker.advance(), Ok(Some(Flag("--D"))));
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"=x"[..])));
+        assert_eq!(walker.advance(), Ok(Some(Flag("--color"))));
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"ye\xFF"[..])));
+        assert_eq!(walker.advance(), Ok(Some(FlagBytes(b"--col\xFFr"))));
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"red"[..])));
+        // forwarded as written
+        assert_eq!(walker.advance(), Ok(Some(Flag("--define"))));
+        assert_eq!(walker.take_original(), Some(b"--define:x".to_vec()));
+        assert_eq!(walker.advance(), Ok(None));
+
+        // no separators at all
+        let mut walker = CoreWalker::borrowed(&["--a=b"]);
+        walker.long_separators(&[]);
+        assert_eq!(walker.advance(), Ok(Some(Flag("--a=b"))));
+        assert!(!walker.can_parameter());
+
+        // prefixes with a single dash, the longest one wins
+        let args = [&b"-Wall"[..], b"-Wl,-z", b"-W", b"-W\xFF", b"-Wl,x"];
+        let mut walker = CoreWalker::borrowed(&args);
+        assert_eq!(walker.upcoming(), Ok(Some(Flag("-W"))));
+        walker.prefix_option("-W");
+        walker.prefix_option("-Wl,");
+        walker.strict_flags(true);
+        assert_eq!(walker.upcoming(), Ok(Some(Flag("-W"))));
+        assert_eq!(walker.advance(), Ok(Some(Flag("-W"))));
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"all"[..])));
+        assert_eq!(walker.advance(), Ok(Some(Flag("-Wl,"))));
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"-z"[..])));
+        assert_eq!(walker.advance(), Ok(Some(Flag("-W"))));
+        assert!(!walker.can_parameter());
+        assert_eq!(walker.advance(), Ok(Some(Flag("-W"))));
+        assert_eq!(walker.parameter(), Some(Cow::Borrowed(&b"\xFF"[..])));
+        assert_eq!(walker.advance(), Ok(Some(Flag("-Wl,"))));
+        assert_eq!(walker.take_original(), Some(b"-Wl,x".to_vec()));
+        assert_eq!(walker.advance(), Ok(None));
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let args = [
+            &b"foo"[..],
+            b"--fruit=banana",
+            b"-vcred",
+            b"--color=ye\xFF",
+            b"-cx\xFF",
+        ];
+        let same = |a: &[u8], b: &[u8]| a.len() == b.len() && std::ptr::eq(a, b);
+        let mut walker = CoreWalker::borrowed(&args);
+
+        match walker.advance() {
+            Ok(Some(Word(w))) => assert!(same(w, args[0])),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(walker.current_word(), Some(Cow::Borrowed(w)) if same(w, args[0])));
+
+        assert_eq!(walker.advance(), Ok(Some(Flag("--fruit"))));
+        assert!(matches!(walker.parameter(), Some(Cow::Borrowed(p)) if p == b"banana"));
+
+        assert_eq!(walker.advance(), Ok(Some(Flag("-v"))));
+        assert_eq!(walker.advance(), Ok(Some(Flag("-c"))));
+        assert!(matches!(walker.parameter(), Some(Cow::Borrowed(p)) if p == b"red"));
+
+        // the non-unicode part stays borrowed for long flags
+        assert_eq!(walker.advance(), Ok(Some(Flag("--color"))));
+        assert!(matches!(walker.parameter(), Some(Cow::Borrowed(p)) if p == b"ye\xFF"));
+
+        assert_eq!(walker.advance(), Ok(Some(Flag("-c"))));
+        assert_eq!(walker.parameter(), Some(Cow::Owned(b"x\xFF".to_vec())));
+        assert_eq!(walker.advance(), Ok(None));
+    }
+
+    #[test]
+    fn test_walking_does_not_copy() {
+        // Walking must stay linear for the huge argument lists xargs
+        // produces. Rather than timing it, check that no step copies the
+        // arguments: the queue only shrinks and a combi is split in place.
+        let n = 1000;
+        let words: Vec<String> = (0..n).map(|i| format!("file{}", i)).collect();
+        let combi = format!("-{}", "v".repeat(n));
+        let mut args: Vec<&str> = words.iter().map(String::as_str).collect();
+        args.push(&combi);
+
+        let mut walker = CoreWalker::borrowed(&args);
+        for (i, word) in words.iter().enumerate() {
+            assert_eq!(walker.advance(), Ok(Some(Word(word.as_bytes()))));
+            assert_eq!(walker.args.len(), n - i);
+        }
+        for i in 1..=n {
+            assert_eq!(walker.advance(), Ok(Some(Flag("-v"))));
+            match &walker.state {
+                State::SplitFlag {
+                    flag: ShortFlag::Inline { .. },
+                    combi:
+                        Parsed::Short {
+                            flags: Cow::Borrowed(flags),
+                        },
+                    offset,
+                    ..
+                } => {
+                    assert!(std::ptr::eq(*flags, combi.as_str()));
+                    assert_eq!(*offset, i + 1);
+                }
+                State::Flag { .. } if i == n => {}
+                other => panic!("unexpected state {:?}", other),
+            }
+        }
+        assert_eq!(walker.advance(), Ok(None));
+    }
+
+    #[test]
+    fn test_large_argv_is_linear() {
+        use std::time::{Duration, Instant};
+
+        // xargs easily produces this many arguments. Rather than an absolute
+        // budget, which depends on the machine, compare against ten times
+        // fewer arguments: linear walking takes about ten times as long,
+        // quadratic a hundred times.
+        fn walk(n: usize) -> Duration {
+            let mut args: Vec<String> = (0..n).map(|i| format!("file{}", i)).collect();
+            args.push(format!("-{}", "v".repeat(n)));
+            (0..3)
+                .map(|_| {
+                    let start = Instant::now();
+                    let mut walker = CoreWalker::new(&args);
+                    let mut count = 0;
+                    while walker.advance().unwrap().is_some() {
+                        count += 1;
+                    }
+                    assert_eq!(count, 2 * n);
+                    start.elapsed()
+                })
+                .min()
+                .unwrap()
+        }
+
+        let small = walk(10_000);
+        let large = walk(100_000);
+        assert!(
+            large < small * 40,
+            "walking 10 times as many items took {:?} instead of {:?}",
+            large,
+            small
+        );
+    }
 }
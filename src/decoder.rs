@@ -0,0 +1,256 @@
+use std::{borrow::Cow, ffi::OsStr};
+
+use crate::{oschars, ArgError};
+
+/**
+A legacy encoding to fall back on for words and parameters that are not valid
+UTF-8, see [`ArgWalker::add_decoder`][crate::ArgWalker::add_decoder].
+
+The crate only provides [`Latin1`]. Other encodings such as Shift-JIS can be
+supported by implementing this trait, for example on top of a crate like
+`encoding_rs`.
+
+Decoders must be [`Send`] and [`Sync`] so that an [`ArgWalker`][crate::ArgWalker]
+holding them can still be moved to and shared with other threads.
+
+# Example
+
+```
+# use argwalker::Decoder;
+/// ASCII plus the Euro sign of Windows-1252.
+struct Euro;
+
+impl Decoder for Euro {
+    fn name(&self) -> &'static str {
+        "ASCII+Euro"
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<String> {
+        bytes
+            .iter()
+            .map(|&b| match b {
+                0x00..=0x7F => Some(b as char),
+                0x80 => Some('€'),
+                _ => None,
+            })
+            .collect()
+    }
+}
+```
+*/
+pub trait Decoder: Send + Sync {
+    /// The name of the encoding, for example `"ISO-8859-1"`.
+    fn name(&self) -> &'static str;
+
+    /// Decode the bytes, or return `None` if they are not valid in this
+    /// encoding.
+    fn decode(&self, bytes: &[u8]) -> Option<String>;
+}
+
+/// ISO-8859-1, in which every byte stands for the code point with the same
+/// number. Decoding never fails, so this should be the last decoder.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Latin1;
+
+impl Decoder for Latin1 {
+    fn name(&self) -> &'static str {
+        "ISO-8859-1"
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<String> {
+        Some(bytes.iter().map(|&b| b as char).collect())
+    }
+}
+
+/// What to do with arguments that are not valid Unicode and cannot be
+/// decoded, see [`ArgWalker::unicode_policy`][crate::ArgWalker::unicode_policy].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnicodePolicy {
+    /// Return [`ArgError::InvalidUnicode`].
+    #[default]
+    Strict,
+    /// Replace the offending parts with `U+FFFD REPLACEMENT CHARACTER`.
+    Lossy,
+    /// Like `Lossy`, but also record an [`ArgError::InvalidUnicode`] holding
+    /// the original argument as a warning.
+    LossyWarn,
+}
+
+/// Text converted to a string by [`Converter::convert`].
+#[derive(Debug)]
+pub struct Converted<'a> {
+    pub text: Cow<'a, str>,
+    /// The name of the legacy encoding, if one was used.
+    pub encoding: Option<&'static str>,
+    /// Set if the text was converted lossily under
+    /// [`UnicodePolicy::LossyWarn`].
+    pub warning: Option<ArgError>,
+}
+
+/// Turns the arguments into strings on behalf of the [`String`] returning
+/// methods of [`ArgWalker`][crate::ArgWalker].
+#[derive(Default)]
+pub struct Converter {
+    decoders: Vec<Box<dyn Decoder>>,
+    policy: UnicodePolicy,
+}
+
+impl Converter {
+    pub fn add_decoder(&mut self, decoder: Box<dyn Decoder>) {
+        self.decoders.push(decoder);
+    }
+
+    pub fn set_policy(&mut self, policy: UnicodePolicy) {
+        self.policy = policy;
+    }
+
+    /// Convert valid Unicode as-is. Otherwise try the decoders in order,
+    /// but only on Unix as elsewhere arguments are never in a legacy
+    /// encoding. Then fall back on the policy.
+    pub fn convert<'a>(&self, s: &'a OsStr) -> Result<Converted<'a>, ArgError> {
+        self.convert_with(s, &self.decoders)
+    }
+
+    /// Like [`convert`][Converter::convert] but without the decoders,
+    /// because flags are never in a legacy encoding.
+    pub fn convert_flag<'a>(&self, s: &'a OsStr) -> Result<Converted<'a>, ArgError> {
+        self.convert_with(s, &[])
+    }
+
+    fn convert_with<'a>(
+        &self,
+        s: &'a OsStr,
+        decoders: &[Box<dyn Decoder>],
+    ) -> Result<Converted<'a>, ArgError> {
+        if let Some(text) = s.to_str() {
+            return Ok(Converted {
+                text: Cow::Borrowed(text),
+                encoding: None,
+                warning: None,
+            });
+        }
+        if cfg!(unix) {
+            let bytes = oschars::as_bytes(s);
+            for decoder in decoders {
+                if let Some(text) = decoder.decode(bytes) {
+                    return Ok(Converted {
+                        text: Cow::Owned(text),
+                        encoding: Some(decoder.name()),
+                        warning: None,
+                    });
+                }
+            }
+        }
+        let err = ArgError::InvalidUnicode(s.to_os_string());
+        let warning = match self.policy {
+            UnicodePolicy::Strict => return Err(err),
+            UnicodePolicy::Lossy => None,
+            UnicodePolicy::LossyWarn => Some(err),
+        };
+        Ok(Converted {
+            text: s.to_string_lossy(),
+            encoding: None,
+            warning,
+        })
+    }
+}
+
+#[test]
+fn test_converter() {
+    use crate::oschars::bad_text as bad;
+
+    struct Strict;
+    impl Decoder for Strict {
+        fn name(&self) -> &'static str {
+            "strict"
+        }
+        fn decode(&self, _bytes: &[u8]) -> Option<String> {
+            None
+        }
+    }
+
+    let mut converter = Converter::default();
+    assert!(matches!(
+        converter.convert("banana".as_ref()),
+        Ok(Converted {
+            text: Cow::Borrowed("banana"),
+            encoding: None,
+            warning: None
+        })
+    ));
+    assert_eq!(
+        converter.convert(&bad("banana")).unwrap_err(),
+        ArgError::InvalidUnicode(bad("banana"))
+    );
+
+    converter.add_decoder(Box::new(Strict));
+    assert!(converter.convert(&bad("banana")).is_err());
+
+    converter.add_decoder(Box::new(Latin1));
+    let banana = bad("banana");
+    let converted = converter.convert(&banana);
+    if cfg!(unix) {
+        let converted = converted.unwrap();
+        assert_eq!(converted.text, "banana\u{FF}");
+        assert_eq!(converted.encoding, Some("ISO-8859-1"));
+    } else {
+        assert!(converted.is_err());
+    }
+
+    converter.set_policy(UnicodePolicy::LossyWarn);
+    let converted = converter.convert_flag(&banana).unwrap();
+    assert!(converted.text.starts_with("banana\u{FFFD}"));
+    assert_eq!(converted.encoding, None);
+    assert_eq!(
+        converted.warning,
+        Some(ArgError::InvalidUnicode(bad("banana")))
+    );
+
+    converter.set_policy(UnicodePolicy::Lossy);
+    let converted = converter.convert_flag(&banana).unwrap();
+    assert!(converted.text.starts_with("banana\u{FFFD}"));
+    assert_eq!(converted.warning, None);
+}
+
+#[test]
+fn test_lossy_flags() {
+    use crate::oschars::bad_text as bad;
+    use crate::ArgWalker;
+    use std::ffi::OsString;
+
+    let mut args = ArgWalker::new(&[OsString::from("word"), bad("-v")]);
+    args.allow_invalid_flags(true);
+    args.unicode_policy(UnicodePolicy::Lossy);
+    let mut skipped = vec![];
+    assert_eq!(args.take_flag(&mut skipped), Ok(Some("-v")));
+    assert_eq!(skipped, ["word"]);
+    let tail = bad("-").to_string_lossy().into_owned();
+    assert_eq!(args.take_flag(&mut skipped), Ok(Some(tail.as_str())));
+    assert_eq!(args.take_flag(&mut skipped), Ok(None));
+}
+
+#[test]
+fn test_walkers_are_send_and_sync() {
+    fn check<T: Send + Sync>() {}
+    check::<crate::ArgWalker<'static>>();
+    check::<crate::ByteWalker<'static>>();
+}
+
+#[test]
+fn test_forward_invalid_unicode() {
+    use crate::oschars::bad_text as bad;
+    use crate::{ArgWalker, Item};
+    use std::ffi::OsString;
+
+    let args = [bad("word"), bad("--flag"), OsString::from("-v")];
+    let mut walker = ArgWalker::new(&args);
+    let mut forwarded = vec![];
+    assert!(walker.take_item().is_err());
+    walker.forward(&mut forwarded);
+    assert!(walker.take_item().is_err());
+    walker.forward(&mut forwarded);
+    assert_eq!(walker.take_item(), Ok(Some(Item::Flag("-v"))));
+    assert_eq!(walker.take_item(), Ok(None));
+    assert_eq!(forwarded, [bad("word"), bad("--flag")]);
+    assert_eq!(walker.finish(), Ok(()));
+}
@@ -0,0 +1,309 @@
+/*!
+A lossless, readable text form for arguments that are not valid Unicode.
+
+[`OsStr::to_string_lossy`][std::ffi::OsStr::to_string_lossy] replaces
+everything it cannot decode with `�`, so the exact argument is lost. The
+functions in this module instead write the problematic parts as escapes:
+
+* `\xFF` for a byte that is not part of valid UTF-8, as found on Unix,
+
+* `\u{D800}` for an unpaired surrogate, as found on Windows,
+
+* `\\` for a backslash, `\t`, `\n` and `\r` for tab, newline and carriage
+  return, and `\u{1B}` and the like for other control characters.
+
+Everything else is written as-is, so the result is valid Unicode that reads
+like the original argument. [`unescape`] turns it back into the original
+[`OsString`][std::ffi::OsString], so it can be copied from a log or an error
+message and passed to the program again.
+
+Escapes that cannot be represented on the current platform are converted the
+same way as by [`ArgWalker::from_byte_args`][crate::ArgWalker::from_byte_args]
+and [`ArgWalker::from_wide_args`][crate::ArgWalker::from_wide_args].
+
+[`escape_bytes`] and [`unescape_bytes`] do the same for byte strings and are
+also available without the `std` feature.
+
+# Example
+
+```
+# #[cfg(feature = "std")] {
+# use argwalker::escape::{escape, unescape};
+# use std::ffi::OsString;
+assert_eq!(escape("tab\there".as_ref()), r"tab\there");
+assert_eq!(escape(r"C:\Temp".as_ref()), r"C:\\Temp");
+assert_eq!(unescape(r"C:\\Temp").unwrap(), OsString::from(r"C:\Temp"));
+
+# #[cfg(unix)] {
+use std::os::unix::ffi::OsStrExt;
+let bad = std::ffi::OsStr::from_bytes(b"caf\xE9.txt");
+assert_eq!(escape(bad), r"caf\xE9.txt");
+assert_eq!(unescape(r"caf\xE9.txt").unwrap(), bad);
+# }
+# }
+```
+*/
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, str};
+
+#[cfg(feature = "std")]
+use std::ffi::{OsStr, OsString};
+
+use crate::ByteError;
+#[cfg(feature = "std")]
+use crate::{oschars, ArgError};
+
+/// Escape a byte string. Bytes that are not part of valid UTF-8 are
+/// written as `\xFF`.
+///
+/// # Example
+/// ```
+/// # use argwalker::escape::escape_bytes;
+/// assert_eq!(escape_bytes(b"caf\xC3\xA9 caf\xE9"), r"café caf\xE9");
+/// ```
+pub fn escape_bytes(bytes: &[u8]) -> String {
+    Escaped::bytes(bytes).to_string()
+}
+
+/// The reverse of [`escape_bytes`].
+///
+/// Returns [`ByteError::InvalidEscape`] with the byte offset of the
+/// backslash if an escape is not recognized. Unpaired surrogates such as
+/// `\u{D800}` are encoded the way WTF-8 does it.
+pub fn unescape_bytes(text: &str) -> Result<Vec<u8>, ByteError> {
+    unescape_units(text, false).map_err(ByteError::InvalidEscape)
+}
+
+/// Escape an [`OsStr`], see the [module documentation][self].
+#[cfg(feature = "std")]
+pub fn escape(s: &OsStr) -> String {
+    Escaped::os(s).to_string()
+}
+
+/// The reverse of [`escape`].
+///
+/// Returns [`ArgError::InvalidEscape`] with the byte offset of the backslash
+/// if an escape is not recognized.
+#[cfg(feature = "std")]
+pub fn unescape(text: &str) -> Result<OsString, ArgError> {
+    let bytes = unescape_units(text, !cfg!(unix)).map_err(ArgError::InvalidEscape)?;
+    Ok(oschars::from_vec(bytes))
+}
+
+/// Writes the escaped form without allocating, for use in `Display` and
+/// `Debug` implementations.
+pub(crate) struct Escaped<'a> {
+    /// The encoded bytes, see `oschars`.
+    bytes: &'a [u8],
+    /// Whether the bytes are WTF-8, in which case everything that is not
+    /// valid UTF-8 is an encoded surrogate.
+    wtf8: bool,
+}
+
+impl<'a> Escaped<'a> {
+    pub(crate) fn bytes(bytes: &'a [u8]) -> Self {
+        Escaped { bytes, wtf8: false }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn os(s: &'a OsStr) -> Self {
+        Escaped {
+            bytes: oschars::as_bytes(s),
+            wtf8: !cfg!(unix),
+        }
+    }
+}
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rest = self.bytes;
+        while !rest.is_empty() {
+            let (valid, invalid_len) = match str::from_utf8(rest) {
+                Ok(s) => (s, 0),
+                Err(e) => {
+                    let valid = str::from_utf8(&rest[..e.valid_up_to()]).expect("valid_up_to");
+                    (valid, e.error_len().unwrap_or(rest.len() - e.valid_up_to()))
+                }
+            };
+            for c in valid.chars() {
+                match c {
+                    '\\' => f.write_str("\\\\")?,
+                    '\t' => f.write_str("\\t")?,
+                    '\n' => f.write_str("\\n")?,
+                    '\r' => f.write_str("\\r")?,
+                    c if c.is_control() => write!(f, "\\u{{{:X}}}", c as u32)?,
+                    c => fmt::Write::write_char(f, c)?,
+                }
+            }
+            rest = &rest[valid.len()..];
+            if invalid_len == 0 {
+                break;
+            }
+            match decode_surrogate(rest) {
+                Some(unit) if self.wtf8 => {
+                    write!(f, "\\u{{{:X}}}", unit)?;
+                    rest = &rest[3..];
+                }
+                _ => {
+                    for b in &rest[..invalid_len] {
+                        write!(f, "\\x{:02X}", b)?;
+                    }
+                    rest = &rest[invalid_len..];
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", self)
+    }
+}
+
+/// Append a surrogate in its WTF-8 form, which is how Windows' `OsStr`
+/// encodes it and which is never valid UTF-8. A low surrogate that follows
+/// a high surrogate is combined with it into a proper character.
+pub(crate) fn encode_surrogate(unit: u16, buf: &mut Vec<u8>) {
+    debug_assert!((0xD800..=0xDFFF).contains(&unit));
+    if unit >= 0xDC00 && buf.len() >= 3 {
+        if let Some(high @ 0xD800..=0xDBFF) = decode_surrogate(&buf[buf.len() - 3..]) {
+            let code_point = 0x10000 + ((high as u32 - 0xD800) << 10) + (unit as u32 - 0xDC00);
+            let c = char::from_u32(code_point).expect("surrogate pair");
+            buf.truncate(buf.len() - 3);
+            buf.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            return;
+        }
+    }
+    buf.extend_from_slice(&[
+        0xE0 | (unit >> 12) as u8,
+        0x80 | (unit >> 6 & 0x3F) as u8,
+        0x80 | (unit & 0x3F) as u8,
+    ]);
+}
+
+/// The surrogate encoded at the start of `bytes`, if any.
+fn decode_surrogate(bytes: &[u8]) -> Option<u16> {
+    match *bytes {
+        [0xED, b1 @ 0xA0..=0xBF, b2 @ 0x80..=0xBF, ..] => {
+            Some(0xD000 | (b1 as u16 & 0x3F) << 6 | (b2 as u16 & 0x3F))
+        }
+        _ => None,
+    }
+}
+
+/// Parse the escaped form into encoded bytes. With `wtf8`, bytes that are
+/// not valid UTF-8 cannot be represented so they are escaped as low
+/// surrogates, the way [`crate::ArgWalker::from_byte_args`] does it. On error,
+/// returns the offset of the offending backslash.
+fn unescape_units(text: &str, wtf8: bool) -> Result<Vec<u8>, usize> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut pos = 0;
+    while let Some(idx) = text[pos..].find('\\') {
+        let start = pos + idx;
+        out.extend_from_slice(&text.as_bytes()[pos..start]);
+        let rest = &text[start + 1..];
+        let (len, unit) = if let Some(digits) = rest.strip_prefix('x') {
+            let digits = digits.get(..2).ok_or(start)?;
+            (3, Unit::Byte(parse_hex(digits).ok_or(start)? as u8))
+        } else if let Some(digits) = rest.strip_prefix("u{") {
+            let end = digits.find('}').ok_or(start)?;
+            let code_point = parse_hex(&digits[..end]).ok_or(start)?;
+            (end + 3, Unit::CodePoint(code_point))
+        } else {
+            let c = match rest.bytes().next() {
+                Some(b'\\') => '\\',
+                Some(b't') => '\t',
+                Some(b'n') => '\n',
+                Some(b'r') => '\r',
+                _ => return Err(start),
+            };
+            (1, Unit::CodePoint(c as u32))
+        };
+        match unit {
+            Unit::Byte(b) if b >= 0x80 && wtf8 => encode_surrogate(0xDC00 | b as u16, &mut out),
+            Unit::Byte(b) => out.push(b),
+            Unit::CodePoint(cp @ 0xD800..=0xDFFF) => encode_surrogate(cp as u16, &mut out),
+            Unit::CodePoint(cp) => {
+                let c = char::from_u32(cp).ok_or(start)?;
+                out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+        }
+        pos = start + 1 + len;
+    }
+    out.extend_from_slice(&text.as_bytes()[pos..]);
+    Ok(out)
+}
+
+enum Unit {
+    Byte(u8),
+    CodePoint(u32),
+}
+
+/// Parse one to six hex digits. Unlike `from_str_radix` this does not
+/// accept a sign.
+fn parse_hex(digits: &str) -> Option<u32> {
+    if digits.is_empty() || digits.len() > 6 || !digits.bytes().all(|d| d.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok()
+}
+
+#[test]
+fn test_escape() {
+    use alloc::vec;
+
+    let roundtrip = |bytes: &[u8], escaped: &str| {
+        assert_eq!(escape_bytes(bytes), escaped);
+        assert_eq!(unescape_bytes(escaped), Ok(bytes.to_vec()), "{}", escaped);
+    };
+    roundtrip(b"", "");
+    roundtrip(b"banana", "banana");
+    roundtrip("caf\u{E9} \u{1F600}".as_bytes(), "caf\u{E9} \u{1F600}");
+    roundtrip(b"a\\b\tc\nd\re\x1B\x7F", r"a\\b\tc\nd\re\u{1B}\u{7F}");
+    roundtrip(b"\xFF\xC3", r"\xFF\xC3");
+    roundtrip(b"caf\xE9.txt", r"caf\xE9.txt");
+    // an encoded surrogate is just bytes on Unix
+    roundtrip(b"\xED\xA0\x80", r"\xED\xA0\x80");
+
+    // escapes that escape_bytes does not produce
+    assert_eq!(unescape_bytes(r"\x41\u{42}"), Ok(b"AB".to_vec()));
+    assert_eq!(unescape_bytes(r"\u{D800}"), Ok(vec![0xED, 0xA0, 0x80]));
+    assert_eq!(
+        unescape_bytes(r"\u{D83D}\u{DE00}"),
+        Ok("\u{1F600}".as_bytes().to_vec())
+    );
+
+    for (bad, offset) in [
+        (r"\", 0),
+        (r"ab\q", 2),
+        (r"\xF", 0),
+        (r"\x+F", 0),
+        (r"\u{}", 0),
+        (r"\u{110000}", 0),
+        (r"\u{1234567}", 0),
+        (r"x\u{41", 1),
+        (r"\u41", 0),
+    ] {
+        assert_eq!(
+            unescape_bytes(bad),
+            Err(ByteError::InvalidEscape(offset)),
+            "{}",
+            bad
+        );
+    }
+
+    // on Windows, surrogates are escaped rather than their bytes
+    let wtf8 = |bytes: &[u8]| Escaped { bytes, wtf8: true }.to_string();
+    assert_eq!(wtf8(b"a\xED\xA0\x80b"), r"a\u{D800}b");
+    assert_eq!(
+        unescape_units(r"a\u{D800}b", true),
+        Ok(b"a\xED\xA0\x80b".to_vec())
+    );
+    assert_eq!(unescape_units(r"\xFF", true), Ok(b"\xED\xB3\xBF".to_vec()));
+}
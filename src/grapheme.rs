@@ -0,0 +1,164 @@
+//! A small approximation of extended grapheme clusters, see
+//! [Unicode Standard Annex #29](https://www.unicode.org/reports/tr29/), for
+//! splitting combinations of short flags.
+//!
+//! It covers what is likely to show up in a flag: combining marks of the
+//! common scripts, variation selectors, emoji modifiers and tags, emoji
+//! joined with a zero width joiner, and pairs of regional indicators that
+//! make up a country flag. It does not cover, for example, Hangul syllables
+//! written as separate jamo.
+
+/// Characters that extend the preceding character rather than starting a
+/// cluster of their own. Sorted, inclusive ranges.
+const EXTEND: &[(u32, u32)] = &[
+    (0x0300, 0x036F), // Combining Diacritical Marks
+    (0x0483, 0x0489), // Cyrillic
+    (0x0591, 0x05BD), // Hebrew
+    (0x05BF, 0x05BF),
+    (0x05C1, 0x05C2),
+    (0x05C4, 0x05C5),
+    (0x05C7, 0x05C7),
+    (0x0610, 0x061A), // Arabic
+    (0x064B, 0x065F),
+    (0x0670, 0x0670),
+    (0x06D6, 0x06DC),
+    (0x06DF, 0x06E4),
+    (0x06E7, 0x06E8),
+    (0x06EA, 0x06ED),
+    (0x0900, 0x0903), // Devanagari
+    (0x093A, 0x093C),
+    (0x093E, 0x094F),
+    (0x0951, 0x0957),
+    (0x0962, 0x0963),
+    (0x0E31, 0x0E31), // Thai
+    (0x0E34, 0x0E3A),
+    (0x0E47, 0x0E4E),
+    (0x1AB0, 0x1AFF),   // Combining Diacritical Marks Extended
+    (0x1DC0, 0x1DFF),   // Combining Diacritical Marks Supplement
+    (0x200C, 0x200D),   // zero width non-joiner and joiner
+    (0x20D0, 0x20FF),   // Combining Diacritical Marks for Symbols
+    (0x302A, 0x302F),   // CJK tone marks
+    (0x3099, 0x309A),   // kana voiced sound marks
+    (0xFE00, 0xFE0F),   // variation selectors
+    (0xFE20, 0xFE2F),   // Combining Half Marks
+    (0x1F3FB, 0x1F3FF), // emoji skin tone modifiers
+    (0xE0020, 0xE007F), // tags
+    (0xE0100, 0xE01EF), // variation selectors supplement
+];
+
+/// Characters that can be joined into a single emoji with a zero width
+/// joiner. Sorted, inclusive ranges.
+const PICTOGRAPHIC: &[(u32, u32)] = &[
+    (0x00A9, 0x00A9),
+    (0x00AE, 0x00AE),
+    (0x203C, 0x203C),
+    (0x2049, 0x2049),
+    (0x2122, 0x2122),
+    (0x2139, 0x2139),
+    (0x2194, 0x21AA),
+    (0x231A, 0x23FF),
+    (0x24C2, 0x24C2),
+    (0x25AA, 0x25FE),
+    (0x2600, 0x27BF),
+    (0x2934, 0x2935),
+    (0x2B05, 0x2B55),
+    (0x3030, 0x3030),
+    (0x303D, 0x303D),
+    (0x3297, 0x3297),
+    (0x3299, 0x3299),
+    (0x1F000, 0x1F1E5),
+    (0x1F200, 0x1F3FA),
+    (0x1F400, 0x1FAFF),
+];
+
+const ZWJ: char = '\u{200D}';
+
+fn in_table(table: &[(u32, u32)], c: char) -> bool {
+    let c = c as u32;
+    table
+        .binary_search_by(|&(lo, hi)| {
+            if hi < c {
+                core::cmp::Ordering::Less
+            } else if lo > c {
+                core::cmp::Ordering::Greater
+            } else {
+                core::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Whether `c` extends the preceding character, for example a combining
+/// accent. Such a character cannot sensibly stand on its own.
+pub fn is_extend(c: char) -> bool {
+    in_table(EXTEND, c)
+}
+
+fn is_pictographic(c: char) -> bool {
+    in_table(PICTOGRAPHIC, c)
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// The length in bytes of the first cluster of `s`, or 0 if `s` is empty.
+pub fn first_cluster_len(s: &str) -> usize {
+    let mut chars = s.char_indices().peekable();
+    let first = match chars.next() {
+        Some((_, c)) => c,
+        None => return 0,
+    };
+    let mut prev = first;
+    let mut len = first.len_utf8();
+    if is_regional_indicator(first) {
+        if let Some(&(_, c)) = chars.peek() {
+            if is_regional_indicator(c) {
+                chars.next();
+                return len + c.len_utf8();
+            }
+        }
+    }
+    while let Some(&(idx, c)) = chars.peek() {
+        let joined = is_extend(c) || (prev == ZWJ && is_pictographic(c));
+        if !joined {
+            break;
+        }
+        chars.next();
+        prev = c;
+        len = idx + c.len_utf8();
+    }
+    len
+}
+
+#[test]
+fn test_first_cluster_len() {
+    fn first(s: &str) -> &str {
+        &s[..first_cluster_len(s)]
+    }
+    assert_eq!(first(""), "");
+    assert_eq!(first("vx"), "v");
+    assert_eq!(first("\u{E9}x"), "\u{E9}");
+    assert_eq!(first("e\u{301}x"), "e\u{301}");
+    assert_eq!(first("e\u{301}\u{323}"), "e\u{301}\u{323}");
+    // a lone combining mark is a cluster of its own
+    assert_eq!(first("\u{301}x"), "\u{301}");
+    // heart with emoji presentation selector
+    assert_eq!(first("\u{2764}\u{FE0F}x"), "\u{2764}\u{FE0F}");
+    // thumbs up with skin tone
+    assert_eq!(first("\u{1F44D}\u{1F3FD}x"), "\u{1F44D}\u{1F3FD}");
+    // family, joined with ZWJ
+    let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+    assert_eq!(first(&alloc::format!("{}x", family)), family);
+    // flags of the Netherlands and Belgium
+    assert_eq!(
+        first("\u{1F1F3}\u{1F1F1}\u{1F1E7}\u{1F1EA}"),
+        "\u{1F1F3}\u{1F1F1}"
+    );
+    // ZWJ followed by something that is not an emoji
+    assert_eq!(first("a\u{200D}b"), "a\u{200D}");
+
+    assert!(is_extend('\u{301}'));
+    assert!(!is_extend('e'));
+    assert!(!is_extend('\u{1F3FA}'));
+}
@@ -1,6 +1,11 @@
-use std::{ffi::OsStr, fmt};
+use std::{
+    ffi::{OsStr, OsString},
+    fmt,
+};
 
-use crate::ArgError;
+use crate::decoder::{Converted, Converter};
+use crate::escape::Escaped;
+use crate::{ArgError, ByteError, ByteItem};
 
 /**
 Item returned from [`ArgWalker::take_item`][super::ArgWalker::take_item].
@@ -14,12 +19,81 @@ pub enum Item<'a> {
 /**
 Item returned from [`ArgWalker::take_item_os`][super::ArgWalker::take_item_os].
 */
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ItemOs<'a> {
     Flag(&'a str),
+    /// A flag that is not valid Unicode, only returned if enabled with
+    /// [`ArgWalker::allow_invalid_flags`][super::ArgWalker::allow_invalid_flags].
+    FlagOs(&'a OsStr),
     Word(&'a OsStr),
 }
 
+/**
+Owned version of [`Item`], see [`Item::into_owned`].
+
+Unlike [`Item`], it does not borrow from the [`ArgWalker`][super::ArgWalker]
+so it can be kept around while the walker moves on.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ItemBuf {
+    Flag(String),
+    Word(String),
+}
+
+/**
+Owned version of [`ItemOs`], see [`ItemOs::into_owned`].
+
+This is what iterating over an [`ArgWalker`][super::ArgWalker] yields.
+*/
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ItemOsBuf {
+    Flag(String),
+    FlagOs(OsString),
+    Word(OsString),
+}
+
+impl Item<'_> {
+    /// Copy the text into an [`ItemBuf`] so it no longer borrows from the walker.
+    pub fn into_owned(self) -> ItemBuf {
+        match self {
+            Item::Flag(f) => ItemBuf::Flag(f.to_string()),
+            Item::Word(w) => ItemBuf::Word(w.to_string()),
+        }
+    }
+}
+
+impl ItemOs<'_> {
+    /// Copy the text into an [`ItemOsBuf`] so it no longer borrows from the walker.
+    pub fn into_owned(self) -> ItemOsBuf {
+        match self {
+            ItemOs::Flag(f) => ItemOsBuf::Flag(f.to_string()),
+            ItemOs::FlagOs(f) => ItemOsBuf::FlagOs(f.to_os_string()),
+            ItemOs::Word(w) => ItemOsBuf::Word(w.to_os_string()),
+        }
+    }
+}
+
+impl ItemBuf {
+    /// Borrow as an [`Item`], convenient for matching against string literals.
+    pub fn as_item(&self) -> Item<'_> {
+        match self {
+            ItemBuf::Flag(f) => Item::Flag(f),
+            ItemBuf::Word(w) => Item::Word(w),
+        }
+    }
+}
+
+impl ItemOsBuf {
+    /// Borrow as an [`ItemOs`], convenient for matching against string literals.
+    pub fn as_item_os(&self) -> ItemOs<'_> {
+        match self {
+            ItemOsBuf::Flag(f) => ItemOs::Flag(f),
+            ItemOsBuf::FlagOs(f) => ItemOs::FlagOs(f),
+            ItemOsBuf::Word(w) => ItemOs::Word(w),
+        }
+    }
+}
+
 impl fmt::Display for Item<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -33,24 +107,95 @@ impl fmt::Display for ItemOs<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ItemOs::Flag(flag) => flag.fmt(f),
-            ItemOs::Word(word) => word.to_string_lossy().fmt(f),
+            ItemOs::FlagOs(flag) => Escaped::os(flag).fmt(f),
+            ItemOs::Word(word) => Escaped::os(word).fmt(f),
+        }
+    }
+}
+
+/// Like the derived implementation, but with bytes that are not valid
+/// Unicode escaped rather than replaced, see [`escape`][crate::escape].
+impl fmt::Debug for ItemOs<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemOs::Flag(flag) => f.debug_tuple("Flag").field(flag).finish(),
+            ItemOs::FlagOs(flag) => f.debug_tuple("FlagOs").field(&Escaped::os(flag)).finish(),
+            ItemOs::Word(word) => f.debug_tuple("Word").field(&Escaped::os(word)).finish(),
         }
     }
 }
 
-pub fn unicode_item(item: ItemOs<'_>) -> Result<Item<'_>, ArgError> {
+impl fmt::Debug for ItemOsBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_item_os().fmt(f)
+    }
+}
+
+impl fmt::Display for ItemBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_item().fmt(f)
+    }
+}
+
+impl fmt::Display for ItemOsBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_item_os().fmt(f)
+    }
+}
+
+/// Convert to an [`Item`] according to the converter. Everything that is
+/// not valid Unicode as-is is handed to `store`, which returns the text to
+/// use and may keep track of the encoding and warning.
+pub fn unicode_item<'a>(
+    item: ItemOs<'a>,
+    converter: &Converter,
+    store: impl FnOnce(Converted<'a>) -> &'a str,
+) -> Result<Item<'a>, ArgError> {
     match item {
         ItemOs::Flag(f) => Ok(Item::Flag(f)),
+        ItemOs::FlagOs(f) => Ok(Item::Flag(store(converter.convert_flag(f)?))),
         ItemOs::Word(w) => match w.to_str() {
             Some(s) => Ok(Item::Word(s)),
-            None => Err(ArgError::InvalidUnicode(std::ffi::OsString::from(w))),
+            None => Ok(Item::Word(store(converter.convert(w)?))),
         },
     }
 }
 
-pub fn unicode_item_option(item_opt: Option<ItemOs<'_>>) -> Result<Option<Item<'_>>, ArgError> {
+pub fn unicode_item_option<'a>(
+    item_opt: Option<ItemOs<'a>>,
+    converter: &Converter,
+    store: impl FnOnce(Converted<'a>) -> &'a str,
+) -> Result<Option<Item<'a>>, ArgError> {
     match item_opt {
         None => Ok(None),
-        Some(item) => unicode_item(item).map(Some),
+        Some(item) => unicode_item(item, converter, store).map(Some),
+    }
+}
+
+pub fn os_item_option(
+    result: Result<Option<ByteItem<'_>>, ByteError>,
+) -> Result<Option<ItemOs<'_>>, ArgError> {
+    match result? {
+        None => Ok(None),
+        Some(ByteItem::Flag(f)) => Ok(Some(ItemOs::Flag(f))),
+        Some(ByteItem::FlagBytes(f)) => Ok(Some(ItemOs::FlagOs(crate::oschars::from_bytes(f)))),
+        Some(ByteItem::Word(w)) => Ok(Some(ItemOs::Word(crate::oschars::from_bytes(w)))),
+    }
+}
+
+#[test]
+fn test_debug() {
+    let item = ItemOsBuf::Word("a\tb".into());
+    assert_eq!(format!("{:?}", item), r#"Word("a\tb")"#);
+    assert_eq!(format!("{:?}", ItemOs::Flag("-v")), r#"Flag("-v")"#);
+
+    #[cfg(unix)]
+    {
+        let item = ItemOsBuf::FlagOs(crate::oschars::bad_text("--fruit"));
+        assert_eq!(format!("{:?}", item), r#"FlagOs("--fruit\xFF")"#);
+        assert_eq!(
+            format!("{:#?}", item.as_item_os()),
+            "FlagOs(\n    \"--fruit\\xFF\",\n)"
+        );
     }
 }
@@ -20,10 +20,26 @@ filenames can contain arbitrary byte sequences which are not necessarily
 UTF-8, while on Windows, filenames are composed of 16 bit sequences that
 usually but not necessarily can be decoded as UTF-16.
 
+If the arguments arrive as a single string rather than as a list, module
+[`shell_words`] can split them the way a POSIX shell does and module
+[`windows_cmdline`] the way Windows programs do. Module [`records`] reads
+them from NUL or newline separated streams such as the output of
+`find -print0`. Module [`escape`] turns arguments that are not valid Unicode
+into readable text and back, for example for log messages.
+
+Most of the above needs the standard library, which is enabled by the default
+feature `std`. Without it the crate is `no_std` and only needs `alloc`. What
+remains is [`ByteWalker`], which walks arguments given as bytes, and the byte
+string functions of [`escape`].
+
 # Example
 
 ```rust
+# #[cfg(feature = "std")]
 # use argwalker::{ArgWalker,ArgError,Item};
+# #[cfg(not(feature = "std"))]
+# fn main() {}
+# #[cfg(feature = "std")]
 # fn main() -> Result<(), ArgError> {
     let mut w = ArgWalker::new(&["eat", "file1", "-vfbanana", "file2", "file3"]);
 
@@ -49,18 +65,76 @@ usually but not necessarily can be decoded as UTF-16.
 
 */
 
-use std::ffi::{OsStr, OsString};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+#[cfg(all(test, not(feature = "std")))]
+extern crate std;
 
+#[cfg(feature = "std")]
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    mem,
+};
+
+#[cfg(feature = "std")]
+pub use argeditor::ArgEditor;
+#[cfg(feature = "std")]
 pub use argerror::ArgError;
-use corewalker::CoreWalker;
+pub use byteerror::ByteError;
+pub use bytewalker::{ByteItem, ByteWalker};
+#[cfg(feature = "std")]
+use corewalker::{CoreWalker, Parsed};
+#[cfg(feature = "std")]
+use decoder::Converter;
+#[cfg(feature = "std")]
+pub use decoder::{Decoder, Latin1, UnicodePolicy};
 
-use item::unicode_item_option;
-pub use item::{Item, ItemOs};
+#[cfg(feature = "std")]
+use item::{os_item_option, unicode_item_option};
+#[cfg(feature = "std")]
+pub use item::{Item, ItemBuf, ItemOs, ItemOsBuf};
+#[cfg(feature = "std")]
+pub use optiontable::OptionTable;
 
+#[cfg(feature = "std")]
+mod argeditor;
+#[cfg(feature = "std")]
 mod argerror;
+mod byteerror;
+mod bytewalker;
 mod corewalker;
+#[cfg(feature = "std")]
+mod decoder;
+pub mod escape;
+mod grapheme;
+#[cfg(feature = "std")]
 mod item;
+#[cfg(feature = "std")]
+mod optiontable;
+#[cfg(feature = "std")]
 mod oschars;
+#[cfg(feature = "std")]
+pub mod records;
+#[cfg(feature = "std")]
+pub mod shell_words;
+#[cfg(feature = "std")]
+pub mod windows_cmdline;
+
+/// What to do with words that start with a look-alike of a dash or a quote,
+/// see [`ArgWalker::look_alike_policy`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LookAlikePolicy {
+    /// Return them as words.
+    #[default]
+    Allow,
+    /// Return them as words but record a warning.
+    Warn,
+    /// Return an error.
+    Reject,
+}
 
 /**
 Command line argument helper.
@@ -79,12 +153,50 @@ If [`.parameter()`][ArgWalker::parameter] is not called, the next
 call to [`.take_item()`][ArgWalker::take_item] will yield [`ArgError::UnexpectedParameter`].
 
 All [`String`] returning methods have a `_os` variant which returns an [`OsString`] instead.
+
+[`ArgWalker`] also implements [`Iterator`], yielding owned [`ItemOsBuf`]s.
+Because these do not borrow from the walker, they can be collected or held on
+to while calling [`.parameter()`][ArgWalker::parameter].
+
+```
+# use argwalker::{ArgWalker,ArgError,ItemOs,ItemOsBuf};
+# fn main() -> Result<(), ArgError> {
+let mut w = ArgWalker::new(&["-v", "--fruit=banana", "file"]);
+let mut seen = vec![];
+let mut fruit = None;
+while let Some(item) = w.next() {
+    let item = item?;
+    if item.as_item_os() == ItemOs::Flag("--fruit") {
+        fruit = w.parameter(false)?;
+    }
+    seen.push(item);
+}
+assert_eq!(fruit, Some("banana".to_string()));
+assert_eq!(seen.len(), 3);
+assert_eq!(seen[2], ItemOsBuf::Word("file".into()));
+#    Ok(())
+# }
+```
 */
-pub struct ArgWalker {
-    core: CoreWalker,
+#[cfg(feature = "std")]
+pub struct ArgWalker<'a> {
+    core: CoreWalker<'a>,
+    assert_finished: bool,
+    recovering: bool,
+    errors: Vec<ArgError>,
+    warnings: Vec<ArgError>,
+    warn_look_alikes: bool,
+    converter: Converter,
+    encoding: Option<&'static str>,
+    /// Holds the current word if it had to be decoded.
+    text: String,
+    /// Holds the upcoming word if it had to be decoded. Updated whenever
+    /// the core moves, see `with_core`.
+    peeked: Option<String>,
 }
 
-impl ArgWalker {
+#[cfg(feature = "std")]
+impl ArgWalker<'static> {
     /// Construct a new [`ArgWalker`].
     ///
     /// # Examples
@@ -106,11 +218,201 @@ impl ArgWalker {
         T: IntoIterator<Item = S>,
         S: AsRef<OsStr>,
     {
+        ArgWalker::from_core(CoreWalker::from_parsed(
+            args.into_iter()
+                .map(|a| Parsed::new(oschars::as_bytes(a.as_ref())).into_owned()),
+        ))
+    }
+
+    /// Construct a new [`ArgWalker`] from a single string holding a command
+    /// line, which is split into arguments the way a POSIX shell would do it.
+    /// See module [`shell_words`] for the details.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,Item};
+    /// let mut args = ArgWalker::from_shell_words("-v --fruit='red apple' file").unwrap();
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-v"))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("--fruit"))));
+    /// assert_eq!(args.parameter(false), Ok(Some("red apple".to_string())));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Word("file"))));
+    /// ```
+    pub fn from_shell_words(text: &str) -> Result<Self, ArgError> {
+        shell_words::split(text).map(ArgWalker::new)
+    }
+
+    /// Like [`from_shell_words`][ArgWalker::from_shell_words] but takes an
+    /// [`OsStr`]. Any non-Unicode content is preserved exactly.
+    pub fn from_shell_words_os(text: &OsStr) -> Result<Self, ArgError> {
+        shell_words::split_os(text).map(ArgWalker::new)
+    }
+
+    /// Construct a new [`ArgWalker`] from arguments given as bytes, the way
+    /// Unix represents them, on any platform.
+    ///
+    /// On Unix this is equivalent to [`new`][ArgWalker::new]. Elsewhere,
+    /// bytes that are not valid UTF-8 end up in the resulting [`OsString`]s as
+    /// lone surrogates, so they are still treated as invalid Unicode. This
+    /// makes it possible to test how a program deals with Unix arguments
+    /// while running on Windows.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,ArgError,Item};
+    /// let mut args = ArgWalker::from_byte_args(&[b"-vx\xFF".to_vec(), b"file".to_vec()]);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-v"))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-x"))));
+    /// assert!(matches!(args.take_item(), Err(ArgError::InvalidUnicode(_))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Word("file"))));
+    /// ```
+    pub fn from_byte_args<S, T>(args: T) -> Self
+    where
+        T: IntoIterator<Item = S>,
+        S: AsRef<[u8]>,
+    {
+        ArgWalker::new(args.into_iter().map(|a| oschars::to_os_string(a.as_ref())))
+    }
+
+    /// Construct a new [`ArgWalker`] from arguments given as 16 bit code
+    /// units, the way Windows represents them, on any platform.
+    ///
+    /// On Windows this is equivalent to [`new`][ArgWalker::new] with
+    /// [`OsString`]s created by `OsStringExt::from_wide`. Elsewhere, the
+    /// arguments are converted to WTF-8, which turns unpaired surrogates
+    /// into bytes that are not valid UTF-8. This makes it possible to test
+    /// how a program deals with Windows arguments while running on Unix.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,ArgError,Item};
+    /// let flags: Vec<u16> = "-vx".encode_utf16().chain([0xD800]).collect();
+    /// let file: Vec<u16> = "file".encode_utf16().collect();
+    /// let mut args = ArgWalker::from_wide_args(&[flags, file]);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-v"))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-x"))));
+    /// assert!(matches!(args.take_item(), Err(ArgError::InvalidUnicode(_))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Word("file"))));
+    /// ```
+    pub fn from_wide_args<S, T>(args: T) -> Self
+    where
+        T: IntoIterator<Item = S>,
+        S: AsRef<[u16]>,
+    {
+        ArgWalker::new(args.into_iter().map(|a| oschars::to_os_string(a.as_ref())))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> ArgWalker<'a> {
+    /// Construct a new [`ArgWalker`] that reads its arguments on demand.
+    ///
+    /// Unlike [`new`][ArgWalker::new], which reads all arguments up front,
+    /// this only reads an argument from `args` when it is needed, and at
+    /// most one ahead for [`.peek_item()`][ArgWalker::peek_item]. This
+    /// makes it possible to walk a stream of arguments, for example one
+    /// read from standard input, without holding all of them in memory.
+    ///
+    /// Note that [`.finish()`][ArgWalker::finish] does not read the rest of
+    /// the stream, so [`ArgError::UnconsumedArguments`] only lists the first
+    /// argument that has not been taken. [`.take_rest()`][ArgWalker::take_rest]
+    /// on the other hand reads everything that is left.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,Item};
+    /// # use std::ffi::OsString;
+    /// let numbers = (1..).map(|i| OsString::from(i.to_string()));
+    /// let mut args = ArgWalker::new_lazy(numbers);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Word("1"))));
+    /// assert_eq!(args.peek_item(), Ok(Some(Item::Word("2"))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Word("2"))));
+    /// ```
+    ///
+    /// Reading the arguments from standard input, as produced by
+    /// `find -print0`:
+    /// ```no_run
+    /// # use argwalker::{records, ArgWalker};
+    /// # use std::io;
+    /// let stdin = io::stdin();
+    /// let mut args = ArgWalker::new_lazy(records::nul_separated(stdin).map(Result::unwrap));
+    /// ```
+    pub fn new_lazy<T>(args: T) -> Self
+    where
+        T: IntoIterator<Item = OsString>,
+        T::IntoIter: Send + 'a,
+    {
+        ArgWalker::from_core(CoreWalker::from_source(Box::new(
+            args.into_iter().map(OsString::into_encoded_bytes),
+        )))
+    }
+
+    /// Construct a new [`ArgWalker`] that borrows its arguments instead of
+    /// copying them.
+    ///
+    /// Words, long flags and parameters are not copied but refer to the
+    /// original arguments. Use [`.parameter_cow()`][ArgWalker::parameter_cow]
+    /// to obtain parameters without copying them either.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,Item};
+    /// # use std::borrow::Cow;
+    /// # use std::ffi::{OsStr, OsString};
+    /// let argv: Vec<OsString> = vec!["-fbanana".into(), "--color=red".into()];
+    /// let mut args = ArgWalker::new_borrowed(&argv);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-f"))));
+    /// let fruit = args.parameter_cow(false).unwrap().unwrap();
+    /// assert_eq!(fruit, Cow::Borrowed(OsStr::new("banana")));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("--color"))));
+    /// let color = args.parameter_cow(false).unwrap().unwrap();
+    /// assert!(matches!(color, Cow::Borrowed(c) if c == "red"));
+    /// ```
+    pub fn new_borrowed<S: AsRef<OsStr>>(args: &'a [S]) -> Self {
+        ArgWalker::from_core(CoreWalker::from_parsed(
+            args.iter()
+                .map(|a| Parsed::new(oschars::as_bytes(a.as_ref()))),
+        ))
+    }
+
+    fn from_core(core: CoreWalker<'a>) -> Self {
+        let converter = Converter::default();
+        let peeked = Self::decode_upcoming(&core, &converter);
         ArgWalker {
-            core: CoreWalker::new(args),
+            core,
+            assert_finished: false,
+            recovering: false,
+            errors: vec![],
+            warnings: vec![],
+            warn_look_alikes: false,
+            converter,
+            encoding: None,
+            text: String::new(),
+            peeked,
         }
     }
 
+    /// Access the core in order to move it, and update `peeked` afterwards.
+    fn with_core<R>(&mut self, f: impl FnOnce(&mut CoreWalker<'a>) -> R) -> R {
+        let result = f(&mut self.core);
+        self.peeked = Self::decode_upcoming(&self.core, &self.converter);
+        result
+    }
+
+    /// The upcoming item in [`String`] form if decoding it needs a copy, so
+    /// [`.peek_item()`][ArgWalker::peek_item] can hand out a reference.
+    fn decode_upcoming(core: &CoreWalker<'a>, converter: &Converter) -> Option<String> {
+        let item = os_item_option(core.upcoming()).ok()?;
+        let mut decoded = None;
+        let _ = unicode_item_option(item, converter, |converted| match converted.text {
+            Cow::Borrowed(text) => text,
+            Cow::Owned(text) => {
+                decoded = Some(text);
+                ""
+            }
+        });
+        decoded
+    }
+
     /// Look at the upcoming item in [`String`] form without moving on to the next
     ///
     /// # Example
@@ -121,7 +423,11 @@ impl ArgWalker {
     /// assert_eq!(args.peek_item(), Ok(Some(Item::Flag("--foo")))); // didn't change
     /// ```
     pub fn peek_item(&self) -> Result<Option<Item<'_>>, ArgError> {
-        self.peek_item_os().and_then(unicode_item_option)
+        let item = self.peek_item_os()?;
+        unicode_item_option(item, &self.converter, |converted| match converted.text {
+            Cow::Borrowed(text) => text,
+            Cow::Owned(_) => self.peeked.as_deref().expect("decoded when the core moved"),
+        })
     }
 
     /// Look at the upcoming item in [`OsString`] form without moving on to the next
@@ -136,7 +442,7 @@ impl ArgWalker {
     /// assert_eq!(args.peek_item_os(), Ok(Some(ItemOs::Word(&foo)))); // didn't change
     /// ```
     pub fn peek_item_os(&self) -> Result<Option<ItemOs<'_>>, ArgError> {
-        self.core.upcoming()
+        os_item_option(self.core.upcoming())
     }
 
     /// Retrieve the upcoming item in [`String`] form and move on to the next
@@ -150,7 +456,30 @@ impl ArgWalker {
     /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("--bar"))));
     /// ```
     pub fn take_item(&mut self) -> Result<Option<Item<'_>>, ArgError> {
-        self.take_item_os().and_then(unicode_item_option)
+        if self.recovering {
+            while self.skip_error().is_some() {}
+        }
+        self.encoding = None;
+        let _ = self.core.advance();
+        self.peeked = Self::decode_upcoming(&self.core, &self.converter);
+        let item = os_item_option(self.core.current())?;
+        if let Some(ItemOs::Word(word)) = item {
+            Self::warn_look_alike(self.warn_look_alikes, word, &mut self.warnings);
+        }
+        let text = &mut self.text;
+        let encoding = &mut self.encoding;
+        let warnings = &mut self.warnings;
+        unicode_item_option(item, &self.converter, move |converted| {
+            *encoding = converted.encoding;
+            warnings.extend(converted.warning);
+            match converted.text {
+                Cow::Borrowed(s) => s,
+                Cow::Owned(s) => {
+                    *text = s;
+                    text.as_str()
+                }
+            }
+        })
     }
 
     /// Retrieve the upcoming item in [`OsString`] form and move on to the next
@@ -165,7 +494,441 @@ impl ArgWalker {
     /// assert_eq!(args.take_item_os(), Ok(Some(ItemOs::Flag("--bar"))));
     /// ```
     pub fn take_item_os(&mut self) -> Result<Option<ItemOs<'_>>, ArgError> {
-        self.core.advance()
+        if self.recovering {
+            while let Err(e) = self.core.upcoming() {
+                self.errors.push(e.into());
+                self.with_core(|core| {
+                    let _ = core.advance();
+                });
+            }
+        }
+        let _ = self.core.advance();
+        self.peeked = Self::decode_upcoming(&self.core, &self.converter);
+        let item = os_item_option(self.core.current())?;
+        if let Some(ItemOs::Word(word)) = item {
+            Self::warn_look_alike(self.warn_look_alikes, word, &mut self.warnings);
+        }
+        Ok(item)
+    }
+
+    /// If the upcoming item is an error, move past it and return the error.
+    /// Otherwise, do nothing and return `None`.
+    ///
+    /// Whether the upcoming item is an error is decided in the same way as
+    /// [`.peek_item()`][ArgWalker::peek_item] does, so arguments that are not
+    /// valid Unicode are skipped. In recovering mode the error is also
+    /// recorded, see [`.recover_errors()`][ArgWalker::recover_errors].
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,ArgError,Item};
+    /// let mut args = ArgWalker::new(&["--fruit=banana", "foo"]);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("--fruit"))));
+    /// assert_eq!(
+    ///     args.skip_error(),
+    ///     Some(ArgError::UnexpectedParameter("--fruit".to_string()))
+    /// );
+    /// assert_eq!(args.skip_error(), None);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Word("foo"))));
+    /// ```
+    pub fn skip_error(&mut self) -> Option<ArgError> {
+        let err = self.peek_item().err()?;
+        self.with_core(|core| {
+            let _ = core.advance();
+        });
+        if self.recovering {
+            self.errors.push(err.clone());
+        }
+        Some(err)
+    }
+
+    /// Switch recovering mode on or off.
+    ///
+    /// In recovering mode, [`.take_item()`][ArgWalker::take_item] and
+    /// [`.take_item_os()`][ArgWalker::take_item_os] do not return errors but
+    /// record them and move on to the next item.
+    /// Errors that occur elsewhere, for example in
+    /// [`.required_parameter()`][ArgWalker::required_parameter],
+    /// can be added with [`.record_error()`][ArgWalker::record_error].
+    /// All recorded errors are reported by [`.finish()`][ArgWalker::finish].
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,ArgError,Item};
+    /// let mut args = ArgWalker::new(&["--fruit=banana", "-v", "foo", "-f"]);
+    /// args.recover_errors(true);
+    /// let mut words = vec![];
+    /// while let Some(item) = args.take_item().unwrap() {
+    ///     match item {
+    ///         Item::Word(w) => words.push(w.to_string()),
+    ///         Item::Flag("-f") => {
+    ///             if let Err(err) = args.required_parameter(false) {
+    ///                 args.record_error(err);
+    ///             }
+    ///         }
+    ///         Item::Flag(_) => {}
+    ///     }
+    /// }
+    /// assert_eq!(words, vec!["foo"]);
+    /// assert_eq!(args.errors().len(), 2);
+    /// assert!(matches!(args.finish(), Err(ArgError::Multiple(errs)) if errs.len() == 2));
+    /// ```
+    pub fn recover_errors(&mut self, enabled: bool) {
+        self.recovering = enabled;
+    }
+
+    /// Add an error to the list reported by [`.finish()`][ArgWalker::finish].
+    pub fn record_error(&mut self, err: ArgError) {
+        self.errors.push(err);
+    }
+
+    /// The errors recorded so far, see [`.recover_errors()`][ArgWalker::recover_errors].
+    pub fn errors(&self) -> &[ArgError] {
+        &self.errors
+    }
+
+    /// Allow flags that are not valid Unicode.
+    ///
+    /// By default, [`.take_item_os()`][ArgWalker::take_item_os] rejects
+    /// flags such as `--fl\xFFag` with [`ArgError::InvalidUnicode`]. When
+    /// enabled, it returns them as [`ItemOs::FlagOs`] instead, so they can
+    /// be reported or [forwarded][ArgWalker::forward] exactly as written.
+    /// A long flag ends at the first `=`, as usual. In a combination of short
+    /// flags, everything from the first character that is not valid Unicode
+    /// on is returned as a single flag, for example `-\xFFx` after the `-v`
+    /// of `-v\xFFx`, because it cannot be split into characters.
+    ///
+    /// [`.take_item()`][ArgWalker::take_item] still returns
+    /// [`ArgError::InvalidUnicode`] for such flags, unless a lossy
+    /// [`.unicode_policy()`][ArgWalker::unicode_policy] is set.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,ItemOs};
+    /// let mut args = ArgWalker::from_byte_args(&[&b"--fl\xFFag=x"[..], b"-v\xFF"]);
+    /// args.allow_invalid_flags(true);
+    /// let mut forwarded = vec![];
+    /// while let Some(item) = args.take_item_os().unwrap() {
+    ///     match item {
+    ///         ItemOs::Flag("-v") => {}
+    ///         ItemOs::FlagOs(f) => {
+    ///             assert!(f.to_string_lossy().starts_with('-'));
+    ///             args.forward(&mut forwarded);
+    ///         }
+    ///         other => panic!("unexpected {:?}", other),
+    ///     }
+    /// }
+    /// assert_eq!(forwarded.len(), 2);
+    /// assert_eq!(forwarded[0].to_string_lossy(), "--fl\u{FFFD}ag=x");
+    /// assert_eq!(forwarded[1].to_string_lossy(), "-\u{FFFD}");
+    /// ```
+    pub fn allow_invalid_flags(&mut self, enabled: bool) {
+        self.with_core(|core| core.allow_invalid_flags(enabled));
+    }
+
+    /// Split combinations of short flags into grapheme clusters rather than
+    /// characters.
+    ///
+    /// By default `-e\u{301}`, an `e` with a combining acute accent, yields
+    /// the flags `-e` and `-\u{301}`. When enabled, it yields the single
+    /// flag `-e\u{301}` instead, which looks like `-é`. The same goes for
+    /// emoji made up of several characters. A short flag that is nothing but
+    /// a combining mark, as in `-\u{301}v`, is then rejected with
+    /// [`ArgError::LoneCombiningMark`]. The rest of the combination still
+    /// follows, so the `-v` is not lost.
+    ///
+    /// The clusters are determined with a small built-in table that covers
+    /// combining marks of the common scripts, variation selectors, emoji
+    /// modifiers and sequences, and country flags, but not every rule of
+    /// Unicode.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,ArgError,Item};
+    /// let mut args = ArgWalker::new(&["-ve\u{301}", "-\u{301}v"]);
+    /// args.split_graphemes(true);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-v"))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-e\u{301}"))));
+    /// assert_eq!(
+    ///     args.take_item(),
+    ///     Err(ArgError::LoneCombiningMark("-\u{301}".to_string()))
+    /// );
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-v"))));
+    ///
+    /// let mut args = ArgWalker::new(&["-\u{301}v", "x"]);
+    /// args.split_graphemes(true);
+    /// args.recover_errors(true);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-v"))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Word("x"))));
+    /// ```
+    pub fn split_graphemes(&mut self, enabled: bool) {
+        self.with_core(|core| core.split_graphemes(enabled));
+    }
+
+    /// Decide what to do with words that start with a Unicode look-alike of
+    /// a dash or a quote. These typically come from commands copied from a
+    /// document, where a word processor has turned `--verbose` into
+    /// `\u{2013}verbose` with an en dash, or `"file"` into
+    /// `\u{201C}file\u{201D}` with curly quotes.
+    ///
+    /// By default, [`LookAlikePolicy::Allow`], they are returned as words
+    /// like any other. With [`LookAlikePolicy::Warn`] they are still
+    /// returned but an [`ArgError::LookAlikeDash`] with the ASCII spelling
+    /// is recorded, see [`.warnings()`][ArgWalker::warnings]. With
+    /// [`LookAlikePolicy::Reject`] they are not returned but reported as an
+    /// [`ArgError::LookAlikeDash`] instead.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,ArgError,Item,LookAlikePolicy};
+    /// let mut args = ArgWalker::new(&["\u{2013}verbose", "\u{201C}file\u{201D}"]);
+    /// args.look_alike_policy(LookAlikePolicy::Reject);
+    /// let err = args.take_item().unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "argument \u{2013}verbose starts with a look-alike of a dash or quote, \
+    ///      did you mean --verbose?"
+    /// );
+    ///
+    /// args.look_alike_policy(LookAlikePolicy::Warn);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Word("\u{201C}file\u{201D}"))));
+    /// assert_eq!(
+    ///     args.warnings(),
+    ///     &[ArgError::LookAlikeDash(
+    ///         "\u{201C}file\u{201D}".to_string(),
+    ///         "\"file\"".to_string()
+    ///     )]
+    /// );
+    /// ```
+    pub fn look_alike_policy(&mut self, policy: LookAlikePolicy) {
+        self.warn_look_alikes = policy == LookAlikePolicy::Warn;
+        self.with_core(|core| core.reject_look_alikes(policy == LookAlikePolicy::Reject));
+    }
+
+    /// Reject long flags that make no sense with [`ArgError::MalformedFlag`].
+    ///
+    /// By default, `---x` is returned as the flag `---x` and `--=x` as the
+    /// flag `--` with parameter `x`. Long flags with whitespace or control
+    /// characters in their name are returned as-is too. When enabled, these
+    /// are all rejected. Parameters are not checked. Flags that are not valid
+    /// Unicode, see [`.allow_invalid_flags()`][ArgWalker::allow_invalid_flags],
+    /// are checked up to the first invalid part.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,ArgError,Item};
+    /// let mut args = ArgWalker::new(&["---x", "--=x", "--a b", "--fruit=red apple"]);
+    /// args.strict_flags(true);
+    /// assert_eq!(args.take_item(), Err(ArgError::MalformedFlag("---x".to_string())));
+    /// assert_eq!(args.take_item(), Err(ArgError::MalformedFlag("--=x".to_string())));
+    /// assert_eq!(args.take_item(), Err(ArgError::MalformedFlag("--a b".to_string())));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("--fruit"))));
+    /// assert_eq!(args.parameter(false), Ok(Some("red apple".to_string())));
+    /// ```
+    pub fn strict_flags(&mut self, enabled: bool) {
+        self.with_core(|core| core.strict_flags(enabled));
+    }
+
+    /// Treat `-f=banana` as `-f banana`.
+    ///
+    /// By default, the parameter of `-f` in `-f=banana` is `=banana`, just
+    /// like it is `xbanana` in `-fxbanana`. When enabled, an `=` right after
+    /// a short flag is left out of its parameter. This only matters if the
+    /// parameter is asked for, otherwise `=` is still taken to be the next
+    /// flag. Arguments are still [forwarded][ArgWalker::forward] as written.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,Item};
+    /// let mut args = ArgWalker::new(&["-vf=banana"]);
+    /// args.allow_short_equals(true);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-v"))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-f"))));
+    /// assert_eq!(args.parameter(false), Ok(Some("banana".to_string())));
+    /// ```
+    pub fn allow_short_equals(&mut self, enabled: bool) {
+        self.with_core(|core| core.allow_short_equals(enabled));
+    }
+
+    /// Set the characters that separate a long flag from its parameter.
+    ///
+    /// The default is `=`, as in `--fruit=banana`. A long flag is split at
+    /// the first separator, so with `&[':', '=']` both `--fruit:banana` and
+    /// `--fruit=banana` yield `--fruit` with parameter `banana`. With no
+    /// separators at all, long flags never have an attached parameter.
+    /// Separators of individual flags, see
+    /// [`.option_separators()`][ArgWalker::option_separators], take
+    /// precedence.
+    ///
+    /// Arguments that have already been read are split again. Those of a
+    /// walker created with [`ArgWalker::new_borrowed`] are then copied if
+    /// they have a parameter.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,Item};
+    /// let mut args = ArgWalker::new(&["--opt:value", "--fruit=banana"]);
+    /// args.long_separators(&[':']);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("--opt"))));
+    /// assert_eq!(args.parameter(false), Ok(Some("value".to_string())));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("--fruit=banana"))));
+    /// ```
+    pub fn long_separators(&mut self, separators: &[char]) {
+        self.with_core(|core| core.long_separators(separators));
+    }
+
+    /// Set the characters that separate the long flag `flag` from its
+    /// parameter, instead of the ones set with
+    /// [`.long_separators()`][ArgWalker::long_separators].
+    ///
+    /// With no separators at all, `flag` is a prefix that is directly
+    /// followed by its parameter. For example, with `--D` and no separators,
+    /// `--DNAME` yields the flag `--D` with parameter `NAME`. If `flag` is
+    /// followed by something else, such as `--D=NAME` when `--D` has
+    /// separator `:`, the other separators apply.
+    ///
+    /// See also [`OptionTable::configure`].
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,Item};
+    /// let mut args = ArgWalker::new(&["--define:DEBUG", "--D", "--DNDEBUG", "--x:y"]);
+    /// args.option_separators("--define", &[':']);
+    /// args.option_separators("--D", &[]);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("--define"))));
+    /// assert_eq!(args.parameter(false), Ok(Some("DEBUG".to_string())));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("--D"))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("--D"))));
+    /// assert_eq!(args.parameter(false), Ok(Some("NDEBUG".to_string())));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("--x:y"))));
+    /// ```
+    pub fn option_separators(&mut self, flag: &str, separators: &[char]) {
+        self.with_core(|core| core.flag_separators(flag, separators));
+    }
+
+    /// Declare a prefix option, whose parameter directly follows its name,
+    /// such as `-I` in `-I/usr/include`, `-O` in `-O2` or `-Xmx` in
+    /// `-Xmx512m`.
+    ///
+    /// An argument that starts with a declared prefix and is longer than it
+    /// is not split into short flags. For example, `-Wall` yields the flag
+    /// `-W` with parameter `all` instead of `-W`, `-a`, `-l` and `-l`. When
+    /// several prefixes match, the longest one wins, so with both `-W` and
+    /// `-Wl,` declared, `-Wl,--as-needed` yields `-Wl,` with parameter
+    /// `--as-needed`. A prefix on its own, such as `-I` in `-I /usr/include`,
+    /// is an ordinary flag that can take the next argument as its parameter.
+    ///
+    /// For prefixes with a double dash this is the same as
+    /// [`.option_separators()`][ArgWalker::option_separators] with no
+    /// separators. See also [`OptionTable::prefix`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix` does not start with a dash, or is `-` or `--`.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,Item};
+    /// let mut args = ArgWalker::new(&["-Wall", "-Wl,--as-needed", "-I", "/usr/include", "-vx"]);
+    /// args.prefix_option("-W");
+    /// args.prefix_option("-Wl,");
+    /// args.prefix_option("-I");
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-W"))));
+    /// assert_eq!(args.parameter(false), Ok(Some("all".to_string())));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-Wl,"))));
+    /// assert_eq!(args.parameter(false), Ok(Some("--as-needed".to_string())));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-I"))));
+    /// assert_eq!(args.parameter(true), Ok(Some("/usr/include".to_string())));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-v"))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-x"))));
+    /// ```
+    pub fn prefix_option(&mut self, prefix: &str) {
+        self.with_core(|core| core.prefix_option(prefix));
+    }
+
+    /// Fall back on a legacy encoding for words and parameters that are not
+    /// valid UTF-8.
+    ///
+    /// By default, the [`String`] returning methods such as
+    /// [`.take_item()`][ArgWalker::take_item] and
+    /// [`.parameter()`][ArgWalker::parameter] return
+    /// [`ArgError::InvalidUnicode`] for such arguments. With decoders added,
+    /// they are tried in the order they were added and the first one that
+    /// succeeds determines the result. Use
+    /// [`.last_encoding()`][ArgWalker::last_encoding] to find out whether a
+    /// decoder was used.
+    ///
+    /// Flags are never decoded, and neither are the [`OsString`] returning
+    /// methods affected. Decoders are only used on Unix, elsewhere arguments
+    /// are always some form of Unicode.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,Item,Latin1};
+    /// # #[cfg(unix)] {
+    /// let mut args = ArgWalker::from_byte_args(&[&b"caf\xE9"[..], b"-f", b"na\xEFve"]);
+    /// args.add_decoder(Latin1);
+    /// assert_eq!(args.peek_item(), Ok(Some(Item::Word("caf\u{E9}"))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Word("caf\u{E9}"))));
+    /// assert_eq!(args.last_encoding(), Some("ISO-8859-1"));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-f"))));
+    /// assert_eq!(args.last_encoding(), None);
+    /// assert_eq!(args.parameter(true), Ok(Some("na\u{EF}ve".to_string())));
+    /// assert_eq!(args.last_encoding(), Some("ISO-8859-1"));
+    /// # }
+    /// ```
+    pub fn add_decoder(&mut self, decoder: impl Decoder + 'static) {
+        self.converter.add_decoder(Box::new(decoder));
+        self.peeked = Self::decode_upcoming(&self.core, &self.converter);
+    }
+
+    /// The name of the legacy encoding that was used to decode the most
+    /// recent word or parameter returned in [`String`] form, or `None` if it
+    /// was valid UTF-8. After [`.take_rest()`][ArgWalker::take_rest], this
+    /// is the encoding of the last argument that needed one.
+    ///
+    /// See [`.add_decoder()`][ArgWalker::add_decoder].
+    pub fn last_encoding(&self) -> Option<&'static str> {
+        self.encoding
+    }
+
+    /// Decide what the [`String`] returning methods do with arguments that
+    /// are not valid Unicode and cannot be [decoded][ArgWalker::add_decoder].
+    ///
+    /// With the default, [`UnicodePolicy::Strict`], they return
+    /// [`ArgError::InvalidUnicode`]. With [`UnicodePolicy::Lossy`], the
+    /// offending parts are replaced with `U+FFFD REPLACEMENT CHARACTER`
+    /// instead. [`UnicodePolicy::LossyWarn`] does the same but also records
+    /// an [`ArgError::InvalidUnicode`] per argument, see
+    /// [`.warnings()`][ArgWalker::warnings].
+    ///
+    /// Flags that are not valid Unicode are still rejected by
+    /// [`.take_item()`][ArgWalker::take_item] unless
+    /// [`.allow_invalid_flags()`][ArgWalker::allow_invalid_flags] is
+    /// enabled, in which case they are subject to this policy as well.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,ArgError,Item,UnicodePolicy};
+    /// let mut args = ArgWalker::from_byte_args(&[&b"caf\xE9"[..], b"-f", b"\xFF"]);
+    /// args.unicode_policy(UnicodePolicy::LossyWarn);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Word("caf\u{FFFD}"))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-f"))));
+    /// assert_eq!(args.parameter(true), Ok(Some("\u{FFFD}".to_string())));
+    /// assert_eq!(args.warnings().len(), 2);
+    /// assert!(matches!(args.warnings()[0], ArgError::InvalidUnicode(_)));
+    /// ```
+    pub fn unicode_policy(&mut self, policy: UnicodePolicy) {
+        self.converter.set_policy(policy);
+        self.peeked = Self::decode_upcoming(&self.core, &self.converter);
+    }
+
+    /// The warnings recorded so far, see
+    /// [`.unicode_policy()`][ArgWalker::unicode_policy] and
+    /// [`.look_alike_policy()`][ArgWalker::look_alike_policy]. Unlike errors,
+    /// these are not reported by [`.finish()`][ArgWalker::finish].
+    pub fn warnings(&self) -> &[ArgError] {
+        &self.warnings
     }
 
     /// Returns `true` if a parameter is available.
@@ -200,7 +963,7 @@ impl ArgWalker {
         }
 
         if free_standing {
-            if let Ok(Some(ItemOs::Word(_))) = self.core.upcoming() {
+            if let Ok(Some(ByteItem::Word(_))) = self.core.upcoming() {
                 return true;
             }
         }
@@ -209,41 +972,75 @@ impl ArgWalker {
     }
 
     pub fn parameter(&mut self, free_standing: bool) -> Result<Option<String>, ArgError> {
-        match self.parameter_os(free_standing) {
-            Ok(None) => Ok(None),
-            Ok(Some(w)) => match w.into_string() {
-                Ok(s) => Ok(Some(s)),
-                Err(w) => Err(ArgError::InvalidUnicode(w)),
-            },
-            Err(e) => Err(e),
+        self.encoding = None;
+        match self.parameter_cow(free_standing)? {
+            None => Ok(None),
+            Some(p) => self.convert(&p).map(Some),
         }
     }
 
     pub fn parameter_os(&mut self, free_standing: bool) -> Result<Option<OsString>, ArgError> {
-        if let Some(p) = self.core.parameter() {
-            return Ok(Some(p.to_os_string()));
+        self.parameter_cow(free_standing)
+            .map(|p| p.map(Cow::into_owned))
+    }
+
+    /// Like [`.parameter_os()`][ArgWalker::parameter_os] but only copies the
+    /// parameter if necessary. If the walker was created with
+    /// [`ArgWalker::new_borrowed`], the parameter is usually borrowed from
+    /// the original arguments. The only exception is a parameter of a short
+    /// flag that is not valid Unicode.
+    pub fn parameter_cow(
+        &mut self,
+        free_standing: bool,
+    ) -> Result<Option<Cow<'a, OsStr>>, ArgError> {
+        if let Some(p) = self.with_core(CoreWalker::parameter) {
+            return Ok(Some(oschars::from_cow(p)));
         }
 
         if !free_standing {
             return Ok(None);
         }
 
-        let item = match self.core.upcoming()? {
-            Some(ItemOs::Word(_)) => self.core.advance(),
+        match self.core.upcoming()? {
+            Some(ByteItem::Word(_)) => {
+                self.with_core(|core| core.advance().map(drop))?;
+            }
             _ => return Ok(None),
-        };
+        }
+        let word = self.core.current_word();
+        assert!(word.is_some(), "upcoming said Word but got something else");
+        let word = word.map(oschars::from_cow);
+        if let Some(word) = &word {
+            Self::warn_look_alike(self.warn_look_alikes, word, &mut self.warnings);
+        }
+        Ok(word)
+    }
 
-        // we know it's a Word, just have to convince the type checker
-        if let Ok(Some(ItemOs::Word(w))) = item {
-            Ok(Some(w.to_os_string()))
-        } else {
-            panic!("upcoming said Ok(Some(Word)) but I got {:?}", item)
+    /// Record a warning if enabled and `word` starts with a look-alike of a
+    /// dash or a quote, see [`.look_alike_policy()`][ArgWalker::look_alike_policy].
+    fn warn_look_alike(enabled: bool, word: &OsStr, warnings: &mut Vec<ArgError>) {
+        if enabled {
+            if let Some(err) = corewalker::look_alike(oschars::as_bytes(word)) {
+                warnings.push(err.into());
+            }
         }
     }
 
     pub fn required_parameter(&mut self, free_standing: bool) -> Result<String, ArgError> {
-        self.required_parameter_os(free_standing)
-            .and_then(|s| s.into_string().map_err(ArgError::InvalidUnicode))
+        self.encoding = None;
+        let p = self.required_parameter_os(free_standing)?;
+        self.convert(&p)
+    }
+
+    /// Convert according to the decoders and the policy, recording the
+    /// encoding and the warning if any.
+    fn convert(&mut self, s: &OsStr) -> Result<String, ArgError> {
+        let converted = self.converter.convert(s)?;
+        if converted.encoding.is_some() {
+            self.encoding = converted.encoding;
+        }
+        self.warnings.extend(converted.warning);
+        Ok(converted.text.into_owned())
     }
 
     pub fn required_parameter_os(&mut self, free_standing: bool) -> Result<OsString, ArgError> {
@@ -252,7 +1049,7 @@ impl ArgWalker {
         }
 
         if let Some(flag) = self.core.current_flag() {
-            Err(ArgError::ParameterMissing(flag.to_string()))
+            Err(ArgError::ParameterMissing(flag))
         } else {
             panic!(".required_parameter can only be called right after a flag")
         }
@@ -262,13 +1059,200 @@ impl ArgWalker {
         loop {
             match self.peek_item()? {
                 Some(Item::Flag(_)) => break,
-                Some(Item::Word(w)) => skipped.push(String::from(w)),
+                Some(Item::Word(w)) => {
+                    let w = String::from(w);
+                    self.take_item()?;
+                    skipped.push(w);
+                }
                 None => return Ok(None),
             }
         }
-        match self.take_item_os()? {
-            Some(ItemOs::Flag(f)) => Ok(Some(f)),
+        // through take_item, a flag that is not valid Unicode may have been
+        // converted under a lossy policy
+        match self.take_item()? {
+            Some(Item::Flag(f)) => Ok(Some(f)),
             _ => unreachable!(),
         }
     }
+
+    /// Take all remaining arguments in [`String`] form, exactly as they were
+    /// written. Afterwards, the walker is at the end.
+    ///
+    /// See [`.take_rest_os()`][ArgWalker::take_rest_os] for details. If one of
+    /// the arguments is not valid Unicode and cannot be decoded, see
+    /// [`.add_decoder()`][ArgWalker::add_decoder], [`ArgError::InvalidUnicode`]
+    /// is returned but the arguments have been consumed nonetheless.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,Item};
+    /// let mut args = ArgWalker::new(&["-v", "exec", "ls", "-l", "--color=never"]);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-v"))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Word("exec"))));
+    /// assert_eq!(args.take_rest(), Ok(vec![
+    ///     "ls".to_string(),
+    ///     "-l".to_string(),
+    ///     "--color=never".to_string(),
+    /// ]));
+    /// assert_eq!(args.take_item(), Ok(None));
+    /// ```
+    pub fn take_rest(&mut self) -> Result<Vec<String>, ArgError> {
+        self.encoding = None;
+        self.take_rest_os()?
+            .iter()
+            .map(|s| self.convert(s))
+            .collect()
+    }
+
+    /// Take all remaining arguments in [`OsString`] form, exactly as they were
+    /// written. Afterwards, the walker is at the end.
+    ///
+    /// Nothing is interpreted, arguments that look like flags are returned
+    /// as-is. If the current item is a flag split off from a combination of
+    /// short flags, the remainder of the combination is returned as the first
+    /// argument, with a dash in front. For example, after taking `-v` from
+    /// `-vx`, the first argument returned is `-x`.
+    ///
+    /// Returns [`ArgError::UnexpectedParameter`] without consuming anything
+    /// if the parameter of the current long flag has not been taken.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,Item};
+    /// # use std::ffi::OsString;
+    /// let mut args = ArgWalker::new(&["-vx", "--", "-y"]);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-v"))));
+    /// assert_eq!(
+    ///     args.take_rest_os(),
+    ///     Ok(vec![OsString::from("-x"), OsString::from("--"), OsString::from("-y")])
+    /// );
+    /// ```
+    pub fn take_rest_os(&mut self) -> Result<Vec<OsString>, ArgError> {
+        let rest = self.with_core(CoreWalker::take_rest)?;
+        Ok(rest.into_iter().map(oschars::from_vec).collect())
+    }
+
+    /// Append the current item to `forwarded` exactly as it was written.
+    ///
+    /// This is meant for wrappers that handle some flags themselves and pass
+    /// everything else on to another program. If the current item is a flag
+    /// split off from a combination of short flags, the remainder of the
+    /// combination is appended as well, because the other program may
+    /// interpret it as a parameter. For example, with `-vxf`, if `-v` has been
+    /// handled and `-x` is not recognized, `-xf` is forwarded. Similarly,
+    /// long flags are forwarded together with their `=parameter`, if any.
+    /// Arguments rejected with [`ArgError::InvalidUnicode`] can also be
+    /// forwarded this way.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no current item: at the start, after the end,
+    /// after an error other than [`ArgError::InvalidUnicode`], or when the
+    /// argument rejected with that error has already been forwarded.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,Item};
+    /// # use std::ffi::OsString;
+    /// let mut args = ArgWalker::new(&["-vxf", "--fruit=banana", "--verbose", "foo"]);
+    /// let mut forwarded: Vec<OsString> = vec![];
+    /// let mut verbose = false;
+    /// while let Some(item) = args.take_item().unwrap() {
+    ///     match item {
+    ///         Item::Flag("-v") | Item::Flag("--verbose") => verbose = true,
+    ///         _ => args.forward(&mut forwarded),
+    ///     }
+    /// }
+    /// assert!(verbose);
+    /// assert_eq!(forwarded, vec!["-xf", "--fruit=banana", "foo"]);
+    /// ```
+    pub fn forward(&mut self, forwarded: &mut Vec<OsString>) {
+        match self.with_core(CoreWalker::take_original) {
+            Some(text) => forwarded.push(oschars::from_vec(text)),
+            None => panic!(".forward() can only be called right after an item has been taken"),
+        }
+    }
+
+    /// Verify that all arguments have been consumed.
+    ///
+    /// Returns [`ArgError::UnexpectedParameter`] if the parameter of the most
+    /// recent long flag has not been taken, and
+    /// [`ArgError::UnconsumedArguments`] if there are arguments left that
+    /// have not been taken.
+    ///
+    /// If any errors have been recorded, see
+    /// [`.recover_errors()`][ArgWalker::recover_errors], they are reported
+    /// together with the above in an [`ArgError::Multiple`].
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,ArgError,Item};
+    /// let mut args = ArgWalker::new(&["-v", "foo", "bar"]);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("-v"))));
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Word("foo"))));
+    /// assert_eq!(
+    ///     args.finish(),
+    ///     Err(ArgError::UnconsumedArguments(vec!["bar".into()]))
+    /// );
+    ///
+    /// let mut args = ArgWalker::new(&["--fruit=banana"]);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("--fruit"))));
+    /// assert_eq!(
+    ///     args.finish(),
+    ///     Err(ArgError::UnexpectedParameter("--fruit".to_string()))
+    /// );
+    /// ```
+    pub fn finish(mut self) -> Result<(), ArgError> {
+        self.assert_finished = false;
+        let mut errors = mem::take(&mut self.errors);
+        if let Err(e) = self.core.check_finished() {
+            errors.push(e.into());
+        }
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.pop().unwrap()),
+            _ => Err(ArgError::Multiple(errors)),
+        }
+    }
+
+    /// If enabled, dropping the walker without calling
+    /// [`.finish()`][ArgWalker::finish] triggers a debug assertion if any
+    /// arguments or parameters have not been consumed. Useful in tests.
+    ///
+    /// # Example
+    /// ```should_panic
+    /// # use argwalker::ArgWalker;
+    /// let mut args = ArgWalker::new(&["foo", "bar"]);
+    /// args.assert_finished_on_drop(true);
+    /// args.take_item().unwrap();
+    /// // args dropped here, "bar" has not been taken
+    /// ```
+    pub fn assert_finished_on_drop(&mut self, enabled: bool) {
+        self.assert_finished = enabled;
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for ArgWalker<'_> {
+    fn drop(&mut self) {
+        if self.assert_finished && !std::thread::panicking() {
+            let result = self.core.check_finished();
+            debug_assert!(
+                result.is_ok(),
+                "ArgWalker dropped without finishing: {}",
+                result.unwrap_err()
+            );
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Iterator for ArgWalker<'_> {
+    type Item = Result<ItemOsBuf, ArgError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.take_item_os()
+            .map(|item| item.map(ItemOs::into_owned))
+            .transpose()
+    }
 }
@@ -0,0 +1,437 @@
+use std::ffi::{OsStr, OsString};
+
+use crate::corewalker::Separators;
+use crate::{ArgError, ArgWalker, ItemOsBuf};
+
+/**
+Declaration of the flags a program understands.
+
+[`ArgWalker`] itself does not need to know which flags exist, but some
+operations do, for example [`.normalize()`][OptionTable::normalize].
+Every option has one or more names, such as `-v` and `--verbose`. The first
+name is the canonical spelling.
+
+Names must be either a single dash followed by a single character, or a
+double dash followed by at least one character. Only the names of
+[prefix options][OptionTable::prefix] can have more characters after a
+single dash.
+
+# Example
+
+```
+# use argwalker::OptionTable;
+let table = OptionTable::new()
+    .flag(&["--verbose", "-v"])
+    .parameter(&["--fruit", "-f"]);
+assert_eq!(table.canonical("-v"), Some("--verbose"));
+assert_eq!(table.takes_parameter("-f"), Some(true));
+assert_eq!(table.canonical("-x"), None);
+```
+*/
+#[derive(Debug, Clone, Default)]
+pub struct OptionTable {
+    options: Vec<OptionSpec>,
+}
+
+#[derive(Debug, Clone)]
+struct OptionSpec {
+    names: Vec<String>,
+    parameter: bool,
+    separators: Option<Vec<char>>,
+    prefix: bool,
+}
+
+impl OptionTable {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        OptionTable::default()
+    }
+
+    /// Declare an option that does not take a parameter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no names are given, or if a name is not a valid flag.
+    pub fn flag(self, names: &[&str]) -> Self {
+        self.add(names, false)
+    }
+
+    /// Declare an option that requires a parameter, for example `-fbanana`,
+    /// `-f banana`, `--fruit=banana` or `--fruit banana`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no names are given, or if a name is not a valid flag.
+    pub fn parameter(self, names: &[&str]) -> Self {
+        self.add(names, true)
+    }
+
+    fn add(mut self, names: &[&str], parameter: bool) -> Self {
+        assert!(!names.is_empty(), "option must have at least one name");
+        for name in names {
+            let short = name.starts_with('-') && name.chars().count() == 2 && name != &"--";
+            let long = name.starts_with("--") && name.len() > 2;
+            assert!(short || long, "invalid flag name {:?}", name);
+        }
+        self.options.push(OptionSpec {
+            names: names.iter().map(|n| n.to_string()).collect(),
+            parameter,
+            separators: None,
+            prefix: false,
+        });
+        self
+    }
+
+    /// Declare a prefix option, whose parameter directly follows its name,
+    /// for example `-I` in `-I/usr/include`, `-O` in `-O2` or `-Wl,` in
+    /// `-Wl,--as-needed`. The parameter can also be the next argument, as
+    /// in `-I /usr/include`. See [`ArgWalker::prefix_option`], which is
+    /// what [`.configure()`][OptionTable::configure] uses.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no names are given, or if a name does not start with a
+    /// dash or is `-` or `--`.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::OptionTable;
+    /// let table = OptionTable::new()
+    ///     .flag(&["-v"])
+    ///     .prefix(&["-W"])
+    ///     .prefix(&["-Wl,"])
+    ///     .prefix(&["-I", "--include-dir"]);
+    /// assert_eq!(
+    ///     table.normalize(&["-Wall", "-Wl,-z", "-vI", "/usr", "--include-dir/opt"]).unwrap(),
+    ///     vec!["-Wall", "-Wl,-z", "-v", "-I/usr", "-I/opt"]
+    /// );
+    /// ```
+    pub fn prefix(mut self, names: &[&str]) -> Self {
+        assert!(!names.is_empty(), "option must have at least one name");
+        for name in names {
+            let valid = name.starts_with('-') && name.len() > 1 && name != &"--";
+            assert!(valid, "invalid prefix option {:?}", name);
+        }
+        self.options.push(OptionSpec {
+            names: names.iter().map(|n| n.to_string()).collect(),
+            parameter: true,
+            separators: Some(vec![]),
+            prefix: true,
+        });
+        self
+    }
+
+    /// Set the characters that separate the long names of the option
+    /// declared last from their parameter, instead of the `=` of
+    /// `--fruit=banana`. With no separators at all the parameter directly
+    /// follows the name, for example `--DNAME` for an option named `--D`.
+    ///
+    /// These are used by [`.normalize()`][OptionTable::normalize] and
+    /// [`ArgEditor`][crate::ArgEditor], and by an [`ArgWalker`] after
+    /// [`.configure()`][OptionTable::configure]. When writing a parameter,
+    /// the first separator is used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no option has been declared yet, or if the option declared
+    /// last does not take a parameter.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::OptionTable;
+    /// let table = OptionTable::new()
+    ///     .parameter(&["--define", "-D"])
+    ///     .separators(&[':', '='])
+    ///     .parameter(&["--opt"])
+    ///     .separators(&[]);
+    /// assert_eq!(
+    ///     table.normalize(&["--define=X", "-DY", "--optZ"]).unwrap(),
+    ///     vec!["--define:X", "--define:Y", "--optZ"]
+    /// );
+    /// ```
+    pub fn separators(mut self, separators: &[char]) -> Self {
+        let spec = self
+            .options
+            .last_mut()
+            .expect("separators must follow the declaration of an option");
+        assert!(
+            spec.parameter,
+            "option {} takes no parameter",
+            spec.names[0]
+        );
+        spec.separators = Some(separators.to_vec());
+        self
+    }
+
+    /// Apply the [separators][OptionTable::separators] and
+    /// [prefixes][OptionTable::prefix] of the options to `walker`, see
+    /// [`ArgWalker::option_separators`] and [`ArgWalker::prefix_option`].
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::{ArgWalker,Item,OptionTable};
+    /// let table = OptionTable::new().parameter(&["--define"]).separators(&[':']);
+    /// let mut args = ArgWalker::new(&["--define:DEBUG"]);
+    /// table.configure(&mut args);
+    /// assert_eq!(args.take_item(), Ok(Some(Item::Flag("--define"))));
+    /// assert_eq!(args.parameter(false), Ok(Some("DEBUG".to_string())));
+    /// ```
+    pub fn configure(&self, walker: &mut ArgWalker<'_>) {
+        for spec in &self.options {
+            if spec.prefix {
+                for name in &spec.names {
+                    walker.prefix_option(name);
+                }
+            } else if let Some(separators) = &spec.separators {
+                for name in spec.names.iter().filter(|n| n.starts_with("--")) {
+                    walker.option_separators(name, separators);
+                }
+            }
+        }
+    }
+
+    /// The separators to split arguments with, as set up by
+    /// [`.configure()`][OptionTable::configure].
+    pub(crate) fn flag_separators(&self) -> Separators {
+        let mut result = Separators::default();
+        for spec in &self.options {
+            let separators = match &spec.separators {
+                Some(separators) => separators,
+                None => continue,
+            };
+            for name in &spec.names {
+                if spec.prefix || name.starts_with("--") {
+                    result.set_for_flag(name, separators);
+                }
+            }
+        }
+        result
+    }
+
+    /// The separator to write between the long name `flag` and its
+    /// parameter, the first of its [separators][OptionTable::separators].
+    pub(crate) fn long_separator(&self, flag: &str) -> String {
+        match self.find(flag).and_then(|spec| spec.separators.as_ref()) {
+            Some(separators) => separators.first().map_or(String::new(), char::to_string),
+            None => "=".to_string(),
+        }
+    }
+
+    fn find(&self, flag: &str) -> Option<&OptionSpec> {
+        self.options
+            .iter()
+            .find(|spec| spec.names.iter().any(|n| n == flag))
+    }
+
+    /// Returns the canonical name of the option that `flag` belongs to, or
+    /// `None` if it is not in the table.
+    pub fn canonical(&self, flag: &str) -> Option<&str> {
+        self.find(flag).map(|spec| spec.names[0].as_str())
+    }
+
+    /// Returns whether the option that `flag` belongs to takes a parameter,
+    /// or `None` if it is not in the table.
+    pub fn takes_parameter(&self, flag: &str) -> Option<bool> {
+        self.find(flag).map(|spec| spec.parameter)
+    }
+
+    /// Rewrite the arguments into a canonical form, so that invocations that
+    /// mean the same thing produce the same arguments.
+    ///
+    /// Combined short flags are split up, every option is written with its
+    /// canonical name, and parameters are attached to their flag: `--fruit=banana`
+    /// for long flags and `-fbanana` for short flags. An empty parameter
+    /// of a short flag, or of a long flag without a
+    /// [separator][OptionTable::separators], is written as a separate
+    /// argument because it would otherwise get lost.
+    ///
+    /// Words are passed through unchanged and in their original order, as
+    /// is everything after `--`. Non-Unicode words and parameters are
+    /// preserved exactly.
+    ///
+    /// Returns [`ArgError::UnknownFlag`] for flags that are not in the table,
+    /// in addition to the errors [`ArgWalker`] itself may return.
+    ///
+    /// # Example
+    /// ```
+    /// # use argwalker::OptionTable;
+    /// let table = OptionTable::new()
+    ///     .flag(&["--verbose", "-v"])
+    ///     .parameter(&["--fruit", "-f"]);
+    /// let expected = vec!["--verbose", "--fruit=banana", "file"];
+    /// assert_eq!(table.normalize(&["-vf", "banana", "file"]).unwrap(), expected);
+    /// assert_eq!(table.normalize(&["-v", "-fbanana", "file"]).unwrap(), expected);
+    /// assert_eq!(table.normalize(&["--verbose", "--fruit=banana", "file"]).unwrap(), expected);
+    /// ```
+    pub fn normalize<S, T>(&self, args: T) -> Result<Vec<OsString>, ArgError>
+    where
+        T: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut walker = ArgWalker::new(args);
+        self.configure(&mut walker);
+        let mut normalized = vec![];
+        while let Some(item) = walker.take_item_os()? {
+            match item.into_owned() {
+                ItemOsBuf::Word(word) => {
+                    let rest_is_literal = word == "--";
+                    normalized.push(word);
+                    if rest_is_literal {
+                        normalized.extend(walker.take_rest_os()?);
+                    }
+                }
+                // not returned unless enabled
+                ItemOsBuf::FlagOs(flag) => return Err(ArgError::InvalidUnicode(flag)),
+                ItemOsBuf::Flag(flag) => {
+                    let spec = self.find(&flag).ok_or(ArgError::UnknownFlag(flag))?;
+                    let name = &spec.names[0];
+                    if !spec.parameter {
+                        normalized.push(OsString::from(name));
+                        continue;
+                    }
+                    let parameter = walker.required_parameter_os(true)?;
+                    let separator = if name.starts_with("--") {
+                        self.long_separator(name)
+                    } else {
+                        String::new()
+                    };
+                    // without a separator an empty parameter has to stay a
+                    // word of its own
+                    if separator.is_empty() && parameter.is_empty() {
+                        normalized.push(OsString::from(name));
+                        normalized.push(parameter);
+                    } else {
+                        let mut arg = OsString::from(name);
+                        arg.push(separator);
+                        arg.push(parameter);
+                        normalized.push(arg);
+                    }
+                }
+            }
+        }
+        Ok(normalized)
+    }
+}
+
+#[test]
+fn test_normalize() {
+    use crate::oschars::bad_text as bad;
+
+    let table = OptionTable::new()
+        .flag(&["-v", "--verbose"])
+        .flag(&["--quiet", "-q"])
+        .parameter(&["-f", "--fruit"])
+        .parameter(&["--color", "-c"]);
+    let normalize = |args: &[OsString]| table.normalize(args);
+    let oss = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+
+    let expected = oss(&["-v", "--quiet", "-fbanana", "--color=red", "a", "b"]);
+    for args in &[
+        oss(&["-vqfbanana", "-cred", "a", "b"]),
+        oss(&[
+            "--verbose",
+            "-q",
+            "--fruit",
+            "banana",
+            "-c",
+            "red",
+            "a",
+            "b",
+        ]),
+        oss(&["-vq", "--fruit=banana", "--color", "red", "a", "b"]),
+        expected.clone(),
+    ] {
+        assert_eq!(
+            normalize(args).as_ref(),
+            Ok(&expected),
+            "normalizing {:?}",
+            args
+        );
+    }
+
+    // order of words is preserved, as is everything after --
+    assert_eq!(
+        normalize(&oss(&["b", "-v", "a", "--", "-q", "--fruit=x"])),
+        Ok(oss(&["b", "-v", "a", "--", "-q", "--fruit=x"]))
+    );
+
+    // empty parameters
+    assert_eq!(
+        normalize(&oss(&["--fruit=", "-c", ""])),
+        Ok(oss(&["-f", "", "--color="]))
+    );
+
+    // non-unicode
+    assert_eq!(
+        normalize(&[bad("word"), OsString::from("--fruit"), bad("x"), bad("-cx")]),
+        Ok(vec![bad("word"), bad("-fx"), bad("--color=x")])
+    );
+
+    // separators
+    let table = OptionTable::new()
+        .parameter(&["--define", "-D"])
+        .separators(&[':'])
+        .parameter(&["-I", "--include"])
+        .separators(&[])
+        .parameter(&["--library", "-l"])
+        .separators(&[]);
+    assert_eq!(
+        table.normalize(&[
+            OsString::from("--define:A=1"),
+            OsString::from("--include/usr"),
+            bad("--define:x"),
+            bad("--include"),
+        ]),
+        Ok(vec![
+            OsString::from("--define:A=1"),
+            OsString::from("-I/usr"),
+            bad("--define:x"),
+            bad("-I"),
+        ])
+    );
+    assert_eq!(
+        table.normalize(oss(&["-lm", "-l", "", "--include", "", "file"])),
+        Ok(oss(&["--librarym", "--library", "", "-I", "", "file"]))
+    );
+
+    // prefixes, the longest one wins
+    let table = OptionTable::new()
+        .flag(&["-v", "--verbose"])
+        .prefix(&["-W"])
+        .prefix(&["-Wl,", "--linker"])
+        .prefix(&["-O"]);
+    assert_eq!(
+        table.normalize(&[
+            OsString::from("-Wno-error"),
+            OsString::from("-Wl,-z"),
+            OsString::from("--linker-s"),
+            OsString::from("-O"),
+            OsString::from("2"),
+            OsString::from("-vO3"),
+            bad("-W"),
+        ]),
+        Ok(vec![
+            OsString::from("-Wno-error"),
+            OsString::from("-Wl,-z"),
+            OsString::from("-Wl,-s"),
+            OsString::from("-O2"),
+            OsString::from("-v"),
+            OsString::from("-O3"),
+            bad("-W"),
+        ])
+    );
+
+    // errors
+    assert_eq!(
+        normalize(&oss(&["-vx"])),
+        Err(ArgError::UnknownFlag("-x".to_string()))
+    );
+    assert_eq!(
+        normalize(&oss(&["--verbose=yes"])),
+        Err(ArgError::UnexpectedParameter("--verbose".to_string()))
+    );
+    assert_eq!(
+        normalize(&oss(&["-v", "--fruit"])),
+        Err(ArgError::ParameterMissing("--fruit".to_string()))
+    );
+}
@@ -4,12 +4,90 @@
 mod oschars_unix;
 mod oschars_windows;
 
-#[cfg(unix)]
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+};
+
+#[cfg(all(unix, test))]
 pub use oschars_unix::bad_text;
-#[cfg(unix)]
-pub use oschars_unix::split_valid;
 
-#[cfg(windows)]
+#[cfg(all(windows, test))]
 pub use oschars_windows::bad_text;
-#[cfg(windows)]
-pub use oschars_windows::split_valid;
+
+/// A code unit of a platform string: `u8` for the byte strings of Unix and
+/// `u16` for the wide strings of Windows. Implemented in oschars_unix and
+/// oschars_windows respectively, but available on every platform.
+pub trait CodeUnit: Copy {
+    /// Index of the first code unit that is not part of valid Unicode.
+    fn find_first_invalid(units: &[Self]) -> Option<usize>;
+
+    /// Append the text to `buf` in the encoding `OsStr` uses on this
+    /// platform. Must be lossless, and must keep invalid code units invalid
+    /// so the walker treats them the same way on every platform.
+    fn encode(units: &[Self], buf: &mut Vec<u8>);
+}
+
+/// Convert code units of either kind to an [`OsString`] on any platform.
+pub fn to_os_string<U: CodeUnit>(units: &[U]) -> OsString {
+    let mut buf = Vec::with_capacity(units.len());
+    U::encode(units, &mut buf);
+    from_vec(buf)
+}
+
+// The core walker works on the encoded bytes of the arguments: the raw bytes
+// on Unix and WTF-8 on Windows. It only splits them right next to valid
+// UTF-8 and only ever receives bytes from as_bytes or CodeUnit::encode, so
+// whatever it returns can be converted back without checking.
+
+pub fn as_bytes(s: &OsStr) -> &[u8] {
+    s.as_encoded_bytes()
+}
+
+pub fn from_bytes(bytes: &[u8]) -> &OsStr {
+    unsafe {
+        // SAFETY: see above
+        OsStr::from_encoded_bytes_unchecked(bytes)
+    }
+}
+
+pub fn from_vec(bytes: Vec<u8>) -> OsString {
+    unsafe {
+        // SAFETY: see above
+        OsString::from_encoded_bytes_unchecked(bytes)
+    }
+}
+
+pub fn from_cow(bytes: Cow<'_, [u8]>) -> Cow<'_, OsStr> {
+    match bytes {
+        Cow::Borrowed(b) => Cow::Borrowed(from_bytes(b)),
+        Cow::Owned(v) => Cow::Owned(from_vec(v)),
+    }
+}
+
+#[test]
+fn test_code_units() {
+    let bytes = |s: OsString| as_bytes(&s).to_vec();
+
+    // unpaired surrogates become invalid UTF-8, pairs become valid UTF-8
+    let wide: [u16; 6] = [0x2D, 0x66, 0xD83D, 0xDE00, 0xD800, 0x78];
+    assert_eq!(u16::find_first_invalid(&wide), Some(4));
+    assert_eq!(
+        bytes(to_os_string(&wide[..])),
+        b"-f\xF0\x9F\x98\x80\xED\xA0\x80x"
+    );
+    assert_eq!(
+        bytes(to_os_string(&[0xDC00u16, 0xD800])),
+        b"\xED\xB0\x80\xED\xA0\x80"
+    );
+
+    let narrow = b"-f\xF0\x9F\x98\x80\xFFx";
+    assert_eq!(u8::find_first_invalid(narrow), Some(6));
+    if cfg!(unix) {
+        assert_eq!(bytes(to_os_string(&narrow[..])), narrow);
+    }
+    // elsewhere the stray byte is escaped as a lone surrogate
+    let mut escaped = vec![];
+    oschars_unix::escape_invalid(narrow, &mut escaped);
+    assert_eq!(escaped, b"-f\xF0\x9F\x98\x80\xED\xB3\xBFx");
+}
@@ -1,48 +1,41 @@
-use std::ffi::{OsStr, OsString};
+use std::ffi::OsString;
 use std::str;
 
-#[cfg(unix)]
-fn to_bytes(s: &OsStr) -> &[u8] {
-    std::os::unix::ffi::OsStrExt::as_bytes(s)
+use super::{to_os_string, CodeUnit};
+use crate::escape::encode_surrogate;
+
+impl CodeUnit for u8 {
+    fn find_first_invalid(units: &[u8]) -> Option<usize> {
+        match str::from_utf8(units) {
+            Ok(_) => None,
+            Err(e) => Some(e.valid_up_to()),
+        }
+    }
+
+    fn encode(units: &[u8], buf: &mut Vec<u8>) {
+        if cfg!(unix) {
+            buf.extend_from_slice(units);
+        } else {
+            escape_invalid(units, buf);
+        }
+    }
 }
 
-#[cfg(unix)]
-fn from_bytes(bytes: &[u8]) -> &OsStr {
-    std::os::unix::ffi::OsStrExt::from_bytes(bytes)
-}
-
-#[cfg(not(unix))]
-fn to_bytes(s: &OsStr) -> &[u8] {
-    let _ = s;
-    unimplemented!("to_bytes only implemented on Unix")
-}
-
-#[cfg(not(unix))]
-fn from_bytes(bytes: &[u8]) -> &OsStr {
-    let _ = bytes;
-    unimplemented!("from_bytes only implemented on Unix")
-}
-#[allow(dead_code)]
-pub fn split_valid(s: &OsStr) -> (String, OsString) {
-    let bytes = to_bytes(s);
-
-    let valid_to = match str::from_utf8(bytes) {
-        Ok(s) => s.len(),
-        Err(e) => e.valid_up_to(),
-    };
-
-    let valid_head = unsafe {
-        // SAFETY: valid_to was derived from std::from_utf8.
-        str::from_utf8_unchecked(&bytes[..valid_to])
-    };
-    let invalid_tail = from_bytes(&bytes[valid_to..]);
-
-    (valid_head.to_string(), invalid_tail.to_os_string())
+/// Escape every stray byte as a lone low surrogate, the same trick Python
+/// uses to smuggle bytes through strings.
+pub fn escape_invalid(units: &[u8], buf: &mut Vec<u8>) {
+    let mut rest = units;
+    while let Some(idx) = u8::find_first_invalid(rest) {
+        buf.extend_from_slice(&rest[..idx]);
+        encode_surrogate(0xDC00 | rest[idx] as u16, buf);
+        rest = &rest[idx + 1..];
+    }
+    buf.extend_from_slice(rest);
 }
 
 #[allow(dead_code)]
 pub fn bad_text(prefix: &str) -> OsString {
-    let mut s = OsString::from(prefix);
-    s.push(from_bytes(&[0xFF]).to_os_string());
-    s
+    let mut units = prefix.as_bytes().to_vec();
+    units.push(0xFF);
+    to_os_string(&units)
 }
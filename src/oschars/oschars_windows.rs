@@ -1,43 +1,30 @@
-use std::ffi::{OsStr, OsString};
+use std::ffi::OsString;
 
-#[cfg(windows)]
-fn to_wide(s: &OsStr) -> Vec<u16> {
-    std::os::windows::prelude::OsStrExt::encode_wide(s).collect()
-}
-
-#[cfg(windows)]
-fn from_wide(wide: &[u16]) -> OsString {
-    std::os::windows::prelude::OsStringExt::from_wide(wide)
-}
+use super::{to_os_string, CodeUnit};
+use crate::escape::encode_surrogate;
 
-#[cfg(not(windows))]
-fn to_wide(s: &OsStr) -> Vec<u16> {
-    let _ = s;
-    unimplemented!("to_wide only implemented on Windows")
-}
+impl CodeUnit for u16 {
+    fn find_first_invalid(units: &[u16]) -> Option<usize> {
+        find_first_invalid(units)
+    }
 
-#[cfg(not(windows))]
-fn from_wide(wide: &[u16]) -> OsString {
-    let _ = wide;
-    unimplemented!("from_wide only implemented on Windows")
+    fn encode(units: &[u16], buf: &mut Vec<u8>) {
+        // This is WTF-8, which is exactly what OsStr uses on Windows.
+        let mut rest = units;
+        while let Some(idx) = find_first_invalid(rest) {
+            push_utf16(&rest[..idx], buf);
+            encode_surrogate(rest[idx], buf);
+            rest = &rest[idx + 1..];
+        }
+        push_utf16(rest, buf);
+    }
 }
 
-#[allow(dead_code)]
-pub fn split_valid(s: &OsStr) -> (String, OsString) {
-    let wide = to_wide(s);
-
-    let valid_head;
-    let invalid_tail;
-    if let Ok(s) = s.to_os_string().into_string() {
-        valid_head = s;
-        invalid_tail = OsString::from("");
-    } else {
-        let valid_to = find_first_invalid(&wide)
-            .expect("find_first_invalid_should return Some if OsString::into_string() returns Err");
-        valid_head = String::from_utf16(&wide[..valid_to]).unwrap();
-        invalid_tail = from_wide(&wide[valid_to..]);
+fn push_utf16(valid: &[u16], buf: &mut Vec<u8>) {
+    for c in char::decode_utf16(valid.iter().copied()) {
+        let c = c.expect("only called on valid UTF-16");
+        buf.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
     }
-    (valid_head, invalid_tail)
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -198,7 +185,7 @@ fn test_find_first_invalid() {
 
 #[allow(dead_code)]
 pub fn bad_text(prefix: &str) -> OsString {
-    let mut s = OsString::from(prefix);
-    s.push(from_wide(&[0xD800, 0xD840]));
-    s
+    let mut units: Vec<u16> = prefix.encode_utf16().collect();
+    units.extend_from_slice(&[0xD800, 0xD840]);
+    to_os_string(&units)
 }
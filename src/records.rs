@@ -0,0 +1,264 @@
+/*!
+Reading arguments from NUL or newline separated streams.
+
+Programs such as `find -print0`, `git ls-files -z` and `xargs -0` exchange
+lists of file names as records separated by NUL bytes, and on Linux the
+arguments of a running process can be read from `/proc/<pid>/cmdline` in the
+same format. Unlike newlines, NUL bytes cannot occur in file names so this
+works for every file name. Newline separated lists are common as well, for
+example in response files.
+
+[`nul_separated`] and [`newline_separated`] turn an [`io::Read`] into an
+iterator of [`OsString`] arguments. The separator is removed, everything else
+is kept exactly as it is. In particular, a carriage return before a newline is
+not removed. A separator at the very end of the input does not start another
+argument, but empty arguments elsewhere are kept.
+
+On Unix the records can contain arbitrary bytes. On other platforms they
+must be valid UTF-8, otherwise an error of kind [`io::ErrorKind::InvalidData`]
+is returned that wraps an [`ArgError::InvalidUnicode`].
+
+Because the input may come from anywhere, the size of the records can be
+limited with [`.max_len()`][Records::max_len] and [`.max_total()`][Records::max_total].
+Exceeding a limit yields an error of kind [`io::ErrorKind::InvalidData`], after
+which the iterator stops.
+
+# Example
+
+```
+# use argwalker::{records, ArgWalker, Item};
+# fn main() -> std::io::Result<()> {
+let input: &[u8] = b"-v\0file name\0\0";
+let args = records::nul_separated(input)
+    .max_len(4096)
+    .collect::<std::io::Result<Vec<_>>>()?;
+assert_eq!(args, vec!["-v", "file name", ""]);
+
+let mut w = ArgWalker::new(args);
+assert_eq!(w.take_item(), Ok(Some(Item::Flag("-v"))));
+assert_eq!(w.take_item(), Ok(Some(Item::Word("file name"))));
+# Ok(())
+# }
+```
+*/
+
+use std::{
+    ffi::OsString,
+    io::{self, BufRead, BufReader, Read},
+    str,
+};
+
+use crate::{oschars, ArgError};
+
+/// Read arguments separated by NUL bytes.
+pub fn nul_separated<R: Read>(reader: R) -> Records<BufReader<R>> {
+    Records::new(BufReader::new(reader), b'\0')
+}
+
+/// Read arguments separated by newlines.
+pub fn newline_separated<R: Read>(reader: R) -> Records<BufReader<R>> {
+    Records::new(BufReader::new(reader), b'\n')
+}
+
+/// Iterator over the records in a stream, see the [module documentation][self].
+#[derive(Debug)]
+pub struct Records<R> {
+    reader: R,
+    delimiter: u8,
+    max_len: usize,
+    max_total: usize,
+    total: usize,
+    count: usize,
+    failed: bool,
+}
+
+impl<R: BufRead> Records<R> {
+    /// Read records separated by `delimiter` from an [`io::BufRead`].
+    pub fn new(reader: R, delimiter: u8) -> Self {
+        Records {
+            reader,
+            delimiter,
+            max_len: usize::MAX,
+            max_total: usize::MAX,
+            total: 0,
+            count: 0,
+            failed: false,
+        }
+    }
+
+    /// Fail if a single record is longer than `limit` bytes, not counting
+    /// the separator. By default there is no limit.
+    pub fn max_len(mut self, limit: usize) -> Self {
+        self.max_len = limit;
+        self
+    }
+
+    /// Fail if the records together are longer than `limit` bytes, not
+    /// counting the separators. By default there is no limit.
+    pub fn max_total(mut self, limit: usize) -> Self {
+        self.max_total = limit;
+        self
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let delimiter = self.delimiter;
+        let mut record = vec![];
+        loop {
+            let buf = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if buf.is_empty() {
+                // a separator at the end does not start another record
+                return Ok(if record.is_empty() {
+                    None
+                } else {
+                    Some(record)
+                });
+            }
+
+            let (chunk, found) = match buf.iter().position(|&b| b == delimiter) {
+                Some(idx) => (&buf[..idx], true),
+                None => (buf, false),
+            };
+            if record.len() + chunk.len() > self.max_len {
+                return Err(invalid_data(format!(
+                    "argument {} is longer than {} bytes",
+                    self.count + 1,
+                    self.max_len
+                )));
+            }
+            if self.total + chunk.len() > self.max_total {
+                return Err(invalid_data(format!(
+                    "arguments are longer than {} bytes in total",
+                    self.max_total
+                )));
+            }
+            record.extend_from_slice(chunk);
+            self.total += chunk.len();
+            let used = chunk.len() + found as usize;
+            self.reader.consume(used);
+            if found {
+                return Ok(Some(record));
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = io::Result<OsString>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = match self.read_record() {
+            Ok(None) => return None,
+            Ok(Some(record)) => {
+                self.count += 1;
+                bytes_to_os(record).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            Err(e) => Err(e),
+        };
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Raw bytes are only valid encoded bytes on Unix, elsewhere the record
+/// has to be UTF-8.
+fn bytes_to_os(bytes: Vec<u8>) -> Result<OsString, ArgError> {
+    if cfg!(unix) || str::from_utf8(&bytes).is_ok() {
+        Ok(oschars::from_vec(bytes))
+    } else {
+        Err(ArgError::InvalidUnicode(oschars::to_os_string(&bytes)))
+    }
+}
+
+#[test]
+fn test_records() {
+    fn read(input: &[u8], delimiter: u8) -> Vec<OsString> {
+        // a tiny buffer so records span several reads
+        let reader = BufReader::with_capacity(3, input);
+        Records::new(reader, delimiter)
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+    let oss = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+
+    assert_eq!(read(b"", b'\0'), oss(&[]));
+    assert_eq!(read(b"\0", b'\0'), oss(&[""]));
+    assert_eq!(read(b"abc", b'\0'), oss(&["abc"]));
+    assert_eq!(read(b"abc\0", b'\0'), oss(&["abc"]));
+    assert_eq!(
+        read(b"-v\0\0long argument\0x", b'\0'),
+        oss(&["-v", "", "long argument", "x"])
+    );
+    assert_eq!(read(b"a b\r\nc\0d\n\n", b'\n'), oss(&["a b\r", "c\0d", ""]));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        assert_eq!(
+            read(b"\xFF\0\xC3\0", b'\0'),
+            vec![
+                OsString::from_vec(vec![0xFF]),
+                OsString::from_vec(vec![0xC3])
+            ]
+        );
+    }
+}
+
+#[test]
+fn test_limits() {
+    let errors = |records: Records<BufReader<&[u8]>>| {
+        records
+            .map(|r| r.map_err(|e| (e.kind(), e.to_string())))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        errors(nul_separated(&b"abc\0abcd\0ab"[..]).max_len(3)),
+        vec![
+            Ok(OsString::from("abc")),
+            Err((
+                io::ErrorKind::InvalidData,
+                "argument 2 is longer than 3 bytes".to_string()
+            ))
+        ]
+    );
+    assert_eq!(
+        errors(newline_separated(&b"abc\nabcd\nab"[..]).max_total(8)),
+        vec![
+            Ok(OsString::from("abc")),
+            Ok(OsString::from("abcd")),
+            Err((
+                io::ErrorKind::InvalidData,
+                "arguments are longer than 8 bytes in total".to_string()
+            ))
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_utf8() {
+    let result = nul_separated(&b"abc\xFF\0x"[..]).collect::<Vec<_>>();
+    if cfg!(unix) {
+        let expected = [oschars::bad_text("abc"), OsString::from("x")];
+        assert_eq!(
+            result.into_iter().collect::<io::Result<Vec<_>>>().unwrap(),
+            expected
+        );
+    } else {
+        assert_eq!(result.len(), 1);
+        let err = result.into_iter().next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let inner = err.into_inner().unwrap().downcast::<ArgError>().unwrap();
+        assert!(matches!(*inner, ArgError::InvalidUnicode(_)));
+    }
+}
@@ -0,0 +1,577 @@
+/*!
+Splitting a single string into arguments the way a POSIX shell does.
+
+This is useful when command lines are kept as a single string, for example in
+configuration files or in an interactive prompt. The string is only split into
+words, nothing is expanded:
+
+* Words are separated by spaces, tabs and newlines.
+
+* Everything between single quotes is taken literally.
+
+* Between double quotes, a backslash only escapes `$`, `` ` ``, `"`, `\` and
+  newline. Otherwise it is taken literally. Dollar signs and backquotes are
+  taken literally as well, as no expansion takes place.
+
+* Outside quotes, a backslash escapes the next character. A backslash
+  followed by a newline is removed entirely.
+
+* A `#` at the start of a word starts a comment which runs until the end of
+  the line.
+
+* Outside quotes, `$'...'` is a string in which backslash escapes such as
+  `\n`, `\t`, `\'`, `\xFF` and `\u00FC` are recognized. This is not part
+  of older versions of POSIX but it is widely supported, for example by bash
+  and zsh. It allows [`quote`] to represent arbitrary bytes.
+
+Other characters that are special to the shell, such as `|`, `;`, `*` and `~`,
+have no special meaning and end up in the words as-is.
+
+An unterminated quote yields [`ArgError::UnterminatedQuote`], which holds the
+offset of the opening quote.
+
+The reverse operation is provided by [`quote`] and [`quote_arg`], which
+produce text that [`split_os`] turns back into the original arguments.
+
+# Example
+
+```
+# use argwalker::shell_words;
+let words = shell_words::split("-v --fruit='red apple' \"$HOME\"/file # comment");
+assert_eq!(words.unwrap(), vec!["-v", "--fruit=red apple", "$HOME/file"]);
+
+let text = shell_words::quote(&["eat", "--fruit=red apple", "it's"]);
+assert_eq!(text, r#"eat '--fruit=red apple' 'it'\''s'"#);
+```
+*/
+
+use std::{
+    ffi::{OsStr, OsString},
+    fmt::Write,
+};
+
+use crate::{oschars, ArgError};
+
+/// Split a `&str` into words.
+///
+/// Offsets in errors are byte offsets.
+///
+/// Returns [`ArgError::InvalidUnicode`] if a word is not valid UTF-8
+/// because of a `\xFF`-style escape in `$'...'`.
+pub fn split(text: &str) -> Result<Vec<String>, ArgError> {
+    split_units(text.as_bytes())?
+        .into_iter()
+        .map(|w| {
+            String::from_utf8(w)
+                .map_err(|e| ArgError::InvalidUnicode(oschars::to_os_string(e.as_bytes())))
+        })
+        .collect()
+}
+
+/// Split a byte string into words. Bytes that are not part of the shell syntax
+/// are passed through unchanged, even if they are not valid UTF-8.
+///
+/// Offsets in errors are byte offsets.
+pub fn split_bytes(text: &[u8]) -> Result<Vec<Vec<u8>>, ArgError> {
+    split_units(text)
+}
+
+/// Split an [`OsStr`] into words without losing any non-Unicode content.
+///
+/// Offsets in errors are in bytes on Unix and in UTF-16 code units on Windows.
+#[cfg(unix)]
+pub fn split_os(text: &OsStr) -> Result<Vec<OsString>, ArgError> {
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+    let words = split_units(text.as_bytes())?;
+    Ok(words.into_iter().map(OsString::from_vec).collect())
+}
+
+/// Split an [`OsStr`] into words without losing any non-Unicode content.
+///
+/// Offsets in errors are in bytes on Unix and in UTF-16 code units on Windows.
+#[cfg(windows)]
+pub fn split_os(text: &OsStr) -> Result<Vec<OsString>, ArgError> {
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+    let wide: Vec<u16> = text.encode_wide().collect();
+    let words = split_units(&wide)?;
+    Ok(words.iter().map(|w| OsString::from_wide(w)).collect())
+}
+
+/// Split an [`OsStr`] into words. On platforms other than Unix and Windows
+/// the text must be valid Unicode, otherwise [`ArgError::InvalidUnicode`]
+/// is returned.
+#[cfg(not(any(unix, windows)))]
+pub fn split_os(text: &OsStr) -> Result<Vec<OsString>, ArgError> {
+    match text.to_str() {
+        Some(s) => Ok(split(s)?.into_iter().map(OsString::from).collect()),
+        None => Err(ArgError::InvalidUnicode(text.to_os_string())),
+    }
+}
+
+/// Quote a list of arguments so a POSIX shell or [`split_os`] turns it back
+/// into the same list. The arguments are separated by spaces.
+///
+/// See [`quote_arg`] for how each argument is quoted. Additionally, the first
+/// argument is quoted if it contains an equals sign because otherwise the
+/// shell would take it for a variable assignment.
+///
+/// # Example
+/// ```
+/// # use argwalker::shell_words::quote;
+/// assert_eq!(quote(&["ls", "-l", "my file"]), "ls -l 'my file'");
+/// assert_eq!(quote(&["a=b", "c=d"]), "'a=b' c=d");
+/// ```
+pub fn quote<S: AsRef<OsStr>>(args: &[S]) -> String {
+    let mut out = String::new();
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        quote_os_into(arg.as_ref(), i == 0, &mut out);
+    }
+    out
+}
+
+/// Quote a single argument so a POSIX shell or [`split_os`] turns it back
+/// into the same argument.
+///
+/// Arguments that consist only of letters, digits and a few harmless
+/// punctuation characters are left alone. Other arguments are put between
+/// single quotes, unless they contain control characters or are not valid
+/// Unicode. Those are written as `$'...'`, with escapes such as `\n` and `\xFF`
+/// for the problematic parts. On Windows, unpaired surrogates are written
+/// as `\uD800`.
+///
+/// # Example
+/// ```
+/// # use argwalker::shell_words::quote_arg;
+/// assert_eq!(quote_arg("banana"), "banana");
+/// assert_eq!(quote_arg(""), "''");
+/// assert_eq!(quote_arg("red apple"), "'red apple'");
+/// assert_eq!(quote_arg("one\ntwo"), r"$'one\ntwo'");
+/// # #[cfg(unix)] {
+/// use std::os::unix::ffi::OsStrExt;
+/// let bad = std::ffi::OsStr::from_bytes(b"caf\xE9.txt");
+/// assert_eq!(quote_arg(bad), r"$'caf\xE9.txt'");
+/// # }
+/// ```
+pub fn quote_arg<S: AsRef<OsStr> + ?Sized>(arg: &S) -> String {
+    let mut out = String::new();
+    quote_os_into(arg.as_ref(), false, &mut out);
+    out
+}
+
+#[cfg(unix)]
+fn quote_os_into(arg: &OsStr, first: bool, out: &mut String) {
+    quote_units(std::os::unix::ffi::OsStrExt::as_bytes(arg), first, out);
+}
+
+#[cfg(windows)]
+fn quote_os_into(arg: &OsStr, first: bool, out: &mut String) {
+    let wide: Vec<u16> = std::os::windows::ffi::OsStrExt::encode_wide(arg).collect();
+    quote_units(&wide, first, out);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn quote_os_into(arg: &OsStr, first: bool, out: &mut String) {
+    // split_os only accepts valid Unicode here
+    quote_units(arg.to_string_lossy().as_bytes(), first, out);
+}
+
+fn quote_units<T: Unit>(arg: &[T], first: bool, out: &mut String) {
+    let chars = T::decode(arg);
+    let plain = |c: &Result<char, T>| match *c {
+        Ok(c) if c.is_ascii() => {
+            c.is_ascii_alphanumeric() || "-_+/.,:@%".contains(c) || (c == '=' && !first)
+        }
+        Ok(c) => !c.is_control() && !c.is_whitespace(),
+        Err(_) => false,
+    };
+    let printable = |c: &Result<char, T>| matches!(c, Ok(c) if !c.is_control());
+
+    if chars.is_empty() {
+        out.push_str("''");
+    } else if chars.iter().all(plain) {
+        out.extend(chars.iter().map(|c| c.unwrap()));
+    } else if chars.iter().all(printable) {
+        out.push('\'');
+        for c in chars {
+            match c.unwrap() {
+                '\'' => out.push_str("'\\''"),
+                c => out.push(c),
+            }
+        }
+        out.push('\'');
+    } else {
+        out.push_str("$'");
+        for c in chars {
+            match c {
+                Ok('\\') => out.push_str("\\\\"),
+                Ok('\'') => out.push_str("\\'"),
+                Ok('\n') => out.push_str("\\n"),
+                Ok('\r') => out.push_str("\\r"),
+                Ok('\t') => out.push_str("\\t"),
+                Ok(c) if c.is_ascii_control() => write!(out, "\\x{:02X}", c as u32).unwrap(),
+                Ok(c) if c.is_control() => write!(out, "\\u{:04X}", c as u32).unwrap(),
+                Ok(c) => out.push(c),
+                Err(u) => out.push_str(&u.escape()),
+            }
+        }
+        out.push('\'');
+    }
+}
+
+/// Code unit of a string, either a byte or a UTF-16 code unit.
+trait Unit: Copy + PartialEq + From<u8> + std::fmt::Debug {
+    /// Decode into characters, with units that cannot be decoded as errors.
+    fn decode(units: &[Self]) -> Vec<Result<char, Self>>;
+
+    /// Append the given code point, which may be a surrogate. Returns false
+    /// if it cannot be represented.
+    fn push_code_point(out: &mut Vec<Self>, code_point: u32) -> bool;
+
+    /// Escape for use in `$'...'`.
+    fn escape(self) -> String;
+}
+
+impl Unit for u8 {
+    fn decode(mut units: &[u8]) -> Vec<Result<char, u8>> {
+        let mut chars = vec![];
+        while !units.is_empty() {
+            let (valid, rest) = match std::str::from_utf8(units) {
+                Ok(s) => (s, &units[units.len()..]),
+                Err(e) => {
+                    let (valid, rest) = units.split_at(e.valid_up_to());
+                    // SAFETY: valid_up_to says so
+                    (unsafe { std::str::from_utf8_unchecked(valid) }, rest)
+                }
+            };
+            chars.extend(valid.chars().map(Ok));
+            if let Some((&bad, rest)) = rest.split_first() {
+                chars.push(Err(bad));
+                units = rest;
+            } else {
+                units = rest;
+            }
+        }
+        chars
+    }
+
+    fn push_code_point(out: &mut Vec<u8>, code_point: u32) -> bool {
+        match std::char::from_u32(code_point) {
+            Some(c) => {
+                out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn escape(self) -> String {
+        format!("\\x{:02X}", self)
+    }
+}
+
+impl Unit for u16 {
+    fn decode(units: &[u16]) -> Vec<Result<char, u16>> {
+        std::char::decode_utf16(units.iter().copied())
+            .map(|r| r.map_err(|e| e.unpaired_surrogate()))
+            .collect()
+    }
+
+    fn push_code_point(out: &mut Vec<u16>, code_point: u32) -> bool {
+        match std::char::from_u32(code_point) {
+            Some(c) => out.extend_from_slice(c.encode_utf16(&mut [0; 2])),
+            None if code_point <= 0xFFFF => out.push(code_point as u16),
+            None => return false,
+        }
+        true
+    }
+
+    fn escape(self) -> String {
+        format!("\\u{:04X}", self)
+    }
+}
+
+/// The actual splitting, generic over the code unit so it works for both
+/// bytes and UTF-16.
+fn split_units<T: Unit>(input: &[T]) -> Result<Vec<Vec<T>>, ArgError> {
+    let is = |unit: Option<&T>, ascii: u8| unit == Some(&T::from(ascii));
+
+    let mut words = vec![];
+    // None while between words
+    let mut word: Option<Vec<T>> = None;
+    let mut i = 0;
+    while let Some(&c) = input.get(i) {
+        let cur = Some(&c);
+        let next = input.get(i + 1);
+        if is(cur, b' ') || is(cur, b'\t') || is(cur, b'\n') {
+            words.extend(word.take());
+            i += 1;
+        } else if is(cur, b'#') && word.is_none() {
+            while i < input.len() && !is(input.get(i), b'\n') {
+                i += 1;
+            }
+        } else if is(cur, b'\\') {
+            if is(next, b'\n') {
+                // line continuation, does not start a word
+            } else {
+                word.get_or_insert_with(Vec::new).push(*next.unwrap_or(&c));
+            }
+            i += 2;
+        } else if is(cur, b'\'') {
+            let w = word.get_or_insert_with(Vec::new);
+            let start = i;
+            i += 1;
+            loop {
+                match input.get(i) {
+                    None => return Err(ArgError::UnterminatedQuote('\'', start)),
+                    q if is(q, b'\'') => break,
+                    Some(&u) => w.push(u),
+                }
+                i += 1;
+            }
+            i += 1;
+        } else if is(cur, b'"') {
+            let w = word.get_or_insert_with(Vec::new);
+            let start = i;
+            i += 1;
+            loop {
+                let u = match input.get(i) {
+                    None => return Err(ArgError::UnterminatedQuote('"', start)),
+                    Some(&u) => u,
+                };
+                let here = Some(&u);
+                let next = input.get(i + 1);
+                let escapable = next.filter(|&&n| b"$`\"\\".iter().any(|&e| n == T::from(e)));
+                if is(here, b'"') {
+                    break;
+                } else if is(here, b'\\') && is(next, b'\n') {
+                    i += 1;
+                } else if let (true, Some(&n)) = (is(here, b'\\'), escapable) {
+                    w.push(n);
+                    i += 1;
+                } else {
+                    w.push(u);
+                }
+                i += 1;
+            }
+            i += 1;
+        } else if is(cur, b'$') && is(next, b'\'') {
+            let w = word.get_or_insert_with(Vec::new);
+            i = ansi_c_string(input, i + 1, w)?;
+        } else {
+            word.get_or_insert_with(Vec::new).push(c);
+            i += 1;
+        }
+    }
+    words.extend(word);
+
+    Ok(words)
+}
+
+/// Process a `$'...'` string. Index `start` points at the opening quote. Returns
+/// the index just after the closing quote.
+fn ansi_c_string<T: Unit>(input: &[T], start: usize, w: &mut Vec<T>) -> Result<usize, ArgError> {
+    let is = |unit: Option<&T>, ascii: u8| unit == Some(&T::from(ascii));
+    let hex = |unit: Option<&T>| {
+        (b'0'..=b'9')
+            .chain(b'a'..=b'f')
+            .chain(b'A'..=b'F')
+            .find(|&h| is(unit, h))
+    };
+    let simple_escapes: &[(u8, u8)] = &[
+        (b'\\', b'\\'),
+        (b'\'', b'\''),
+        (b'"', b'"'),
+        (b'?', b'?'),
+        (b'a', 0x07),
+        (b'b', 0x08),
+        (b'e', 0x1B),
+        (b'E', 0x1B),
+        (b'f', 0x0C),
+        (b'n', b'\n'),
+        (b'r', b'\r'),
+        (b't', b'\t'),
+        (b'v', 0x0B),
+    ];
+
+    let mut i = start + 1;
+    loop {
+        let u = match input.get(i) {
+            None => return Err(ArgError::UnterminatedQuote('\'', start)),
+            Some(&u) => u,
+        };
+        let next = input.get(i + 1);
+        if is(Some(&u), b'\'') {
+            return Ok(i + 1);
+        } else if !is(Some(&u), b'\\') || next.is_none() {
+            w.push(u);
+            i += 1;
+        } else if let Some(&(_, replacement)) = simple_escapes.iter().find(|(e, _)| is(next, *e)) {
+            w.push(T::from(replacement));
+            i += 2;
+        } else if let Some(max_digits) = [(b'x', 2), (b'u', 4), (b'U', 8)]
+            .iter()
+            .find(|(e, _)| is(next, *e))
+            .map(|(_, n)| *n)
+        {
+            let mut value: u32 = 0;
+            let mut j = i + 2;
+            while j < i + 2 + max_digits {
+                match hex(input.get(j)) {
+                    Some(h) => value = value * 16 + (h as char).to_digit(16).unwrap(),
+                    None => break,
+                }
+                j += 1;
+            }
+            let pushed = if j == i + 2 {
+                false
+            } else if max_digits == 2 {
+                w.push(T::from(value as u8));
+                true
+            } else {
+                T::push_code_point(w, value)
+            };
+            if pushed {
+                i = j;
+            } else {
+                // take the backslash literally
+                w.push(u);
+                i += 1;
+            }
+        } else {
+            // unknown escape, keep the backslash
+            w.push(u);
+            i += 1;
+        }
+    }
+}
+
+#[test]
+fn test_split() {
+    fn check(text: &str, expected: &[&str]) {
+        assert_eq!(
+            split(text),
+            Ok(expected.iter().map(|s| s.to_string()).collect()),
+            "splitting {:?}",
+            text
+        );
+    }
+
+    check("", &[]);
+    check("  \t\n ", &[]);
+    check("a b\tc\nd", &["a", "b", "c", "d"]);
+    check("  a   b  ", &["a", "b"]);
+
+    // quotes
+    check("'a b' \"c d\"", &["a b", "c d"]);
+    check("a'b'\"c\"d", &["abcd"]);
+    check("'' \"\"", &["", ""]);
+    check("'a\\b' 'a\"b'", &["a\\b", "a\"b"]);
+    check("\"a'b\" \"$x `y`\"", &["a'b", "$x `y`"]);
+
+    // backslashes
+    check("a\\ b \\'c\\'", &["a b", "'c'"]);
+    check("a\\\\b \\", &["a\\b", "\\"]);
+    check("a\\\nb \\\n c", &["ab", "c"]);
+    check("\"\\$ \\` \\\" \\\\ \\a \\\n\"", &["$ ` \" \\ \\a "]);
+
+    // comments
+    check("a # b c\nd", &["a", "d"]);
+    check("a#b '#c' \\#d #", &["a#b", "#c", "#d"]);
+
+    // not special
+    check("a|b;c & ~ * $x", &["a|b;c", "&", "~", "*", "$x"]);
+    check("fr\u{FC}it \u{1F34C}", &["fr\u{FC}it", "\u{1F34C}"]);
+
+    // ANSI-C strings
+    check(r"$'a b\'c\n\t\\' x$'y'", &["a b'c\n\t\\", "xy"]);
+    check(
+        r"$'\x41\x4a\x4G\xg\u00fc\U0001F34C\u'",
+        &["AJ\x04G\\xg\u{FC}\u{1F34C}\\u"],
+    );
+    check(r#""$'a'" $"#, &["$'a'", "$"]);
+    assert_eq!(
+        split(r"$'\xFF'"),
+        Err(ArgError::InvalidUnicode(oschars::to_os_string(b"\xFF")))
+    );
+
+    // errors
+    assert_eq!(split("a 'b"), Err(ArgError::UnterminatedQuote('\'', 2)));
+    assert_eq!(split("$'a\\'"), Err(ArgError::UnterminatedQuote('\'', 1)));
+    assert_eq!(split("a \"b\\\""), Err(ArgError::UnterminatedQuote('"', 2)));
+    assert_eq!(
+        split("'a' \"b' c"),
+        Err(ArgError::UnterminatedQuote('"', 4))
+    );
+}
+
+#[test]
+fn test_split_bytes() {
+    assert_eq!(
+        split_bytes(b"a\xFF 'b\xFE c'"),
+        Ok(vec![b"a\xFF".to_vec(), b"b\xFE c".to_vec()])
+    );
+    let wide: Vec<u16> = vec![0xD800, b' ' as u16, b'"' as u16, 0xDC00];
+    assert_eq!(split_units(&wide), Err(ArgError::UnterminatedQuote('"', 2)));
+}
+
+#[test]
+fn test_quote_roundtrip() {
+    // all words up to a certain length over an alphabet of troublemakers
+    let alphabet: &[&[u8]] = &[
+        b"a",
+        b" ",
+        b"'",
+        b"\"",
+        b"\\",
+        b"$",
+        b"#",
+        b"=",
+        b"\n",
+        b"\x01",
+        b"\xFF",
+        "\u{FC}".as_bytes(),
+    ];
+    let mut words: Vec<Vec<u8>> = vec![vec![]];
+    let mut prev = words.clone();
+    for _ in 0..3 {
+        prev = prev
+            .iter()
+            .flat_map(|w| alphabet.iter().map(move |a| [&w[..], a].concat()))
+            .collect();
+        words.extend(prev.iter().cloned());
+    }
+
+    for w in &words {
+        for args in &[
+            vec![w.clone()],
+            vec![w.clone(), w.clone()],
+            vec![b"x".to_vec(), w.clone()],
+        ] {
+            let mut text = String::new();
+            for (i, a) in args.iter().enumerate() {
+                if i > 0 {
+                    text.push(' ');
+                }
+                quote_units(a, i == 0, &mut text);
+            }
+            assert_eq!(
+                &split_bytes(text.as_bytes()).unwrap(),
+                args,
+                "quoted as {}",
+                text
+            );
+        }
+    }
+
+    let wide = vec![b'a' as u16, 0xD800, b'\'' as u16, 0xDC00, 0xD83C, 0xDF4C];
+    let mut text = String::new();
+    quote_units(&wide, false, &mut text);
+    assert_eq!(text, "$'a\\uD800\\'\\uDC00\u{1F34C}'");
+    let text: Vec<u16> = text.encode_utf16().collect();
+    assert_eq!(split_units(&text).unwrap(), vec![wide]);
+}
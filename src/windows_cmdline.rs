@@ -0,0 +1,353 @@
+/*!
+Splitting Windows command lines into separate arguments.
+
+On Windows, programs receive their command line as a single string and it is
+up to the program to split it into arguments. Normally the C runtime or the
+Rust standard library takes care of this, but some programs such as services,
+scheduled tasks and installers are handed a raw command line string.
+
+The functions in this module split such strings using the same rules as the
+Microsoft C runtime and `CommandLineToArgvW`, which is also what the Rust
+standard library uses:
+
+* Arguments are separated by spaces and tabs, except inside double quotes.
+
+* A double quote toggles quoting, unless it is preceded by an odd number of
+  backslashes. Inside quotes, two double quotes in a row yield a literal double
+  quote.
+
+* Backslashes are taken literally unless they are immediately followed by a
+  double quote. In that case every pair of backslashes yields one backslash and
+  a remaining odd backslash turns the double quote into a literal double
+  quote.
+
+* The first argument, the program name, is special. It cannot contain escaped
+  quotes so backslashes are always taken literally, and double quotes only
+  toggle quoting.
+
+The result can be passed to [`ArgWalker::new`][crate::ArgWalker::new].
+
+The reverse operation is provided by [`quote`] and [`quote_wide`], which
+produce a command line that [`split`] turns back into the original arguments.
+
+# Example
+
+```
+# use argwalker::{windows_cmdline, ArgWalker, Item};
+let args = windows_cmdline::split(r#"C:\Tools\eat.exe -v "file name" a\\\"b"#);
+assert_eq!(args, vec![r"C:\Tools\eat.exe", "-v", "file name", r#"a\"b"#]);
+
+let mut w = ArgWalker::new(&args[1..]);
+assert_eq!(w.take_item(), Ok(Some(Item::Flag("-v"))));
+assert_eq!(w.take_item(), Ok(Some(Item::Word("file name"))));
+```
+*/
+
+#[cfg(windows)]
+use std::ffi::{OsStr, OsString};
+
+const SPACE: u16 = b' ' as u16;
+const TAB: u16 = b'\t' as u16;
+const QUOTE: u16 = b'"' as u16;
+const BACKSLASH: u16 = b'\\' as u16;
+
+/// Split a command line given as a `&str`.
+///
+/// # Example
+/// ```
+/// # use argwalker::windows_cmdline::split;
+/// assert_eq!(split(r#"prog a"b"" c d"#), vec!["prog", r#"ab" c d"#]);
+/// ```
+pub fn split(cmdline: &str) -> Vec<String> {
+    let wide: Vec<u16> = cmdline.encode_utf16().collect();
+    split_wide(&wide)
+        .into_iter()
+        .map(|arg| {
+            // Only ASCII characters are removed so surrogate pairs stay intact
+            String::from_utf16(&arg).expect("splitting valid UTF-16 yields valid UTF-16")
+        })
+        .collect()
+}
+
+/// Split a command line given as UTF-16 code units. Unpaired surrogates are
+/// passed through unchanged.
+///
+/// # Example
+/// ```
+/// # use argwalker::windows_cmdline::split_wide;
+/// let wide = |s: &str| s.encode_utf16().collect::<Vec<u16>>();
+/// assert_eq!(split_wide(&wide("prog -v")), vec![wide("prog"), wide("-v")]);
+/// ```
+pub fn split_wide(cmdline: &[u16]) -> Vec<Vec<u16>> {
+    let mut args = vec![];
+    if cmdline.is_empty() {
+        return args;
+    }
+
+    let mut units = cmdline.iter().copied().peekable();
+
+    // The program name does not know about escapes
+    let mut in_quotes = false;
+    let mut cur = vec![];
+    for u in &mut units {
+        match u {
+            QUOTE => in_quotes = !in_quotes,
+            SPACE | TAB if !in_quotes => break,
+            _ => cur.push(u),
+        }
+    }
+    args.push(cur);
+    while let Some(&SPACE) | Some(&TAB) = units.peek() {
+        units.next();
+    }
+
+    let mut cur = vec![];
+    let mut in_quotes = false;
+    while let Some(u) = units.next() {
+        match u {
+            SPACE | TAB if !in_quotes => {
+                args.push(cur);
+                cur = vec![];
+                while let Some(&SPACE) | Some(&TAB) = units.peek() {
+                    units.next();
+                }
+            }
+            BACKSLASH => {
+                let mut count = 1;
+                while units.peek() == Some(&BACKSLASH) {
+                    units.next();
+                    count += 1;
+                }
+                if units.peek() == Some(&QUOTE) {
+                    cur.extend((0..count / 2).map(|_| BACKSLASH));
+                    if count % 2 == 1 {
+                        units.next();
+                        cur.push(QUOTE);
+                    }
+                } else {
+                    cur.extend((0..count).map(|_| BACKSLASH));
+                }
+            }
+            QUOTE if in_quotes => match units.peek() {
+                Some(&QUOTE) => {
+                    units.next();
+                    cur.push(QUOTE);
+                }
+                Some(_) => in_quotes = false,
+                // leave in_quotes set so the empty argument is pushed below
+                None => break,
+            },
+            QUOTE => in_quotes = true,
+            _ => cur.push(u),
+        }
+    }
+    if !cur.is_empty() || in_quotes {
+        args.push(cur);
+    }
+
+    args
+}
+
+/// Split a command line given as an [`OsStr`]. Only available on Windows.
+#[cfg(windows)]
+pub fn split_os(cmdline: &OsStr) -> Vec<OsString> {
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+    let wide: Vec<u16> = cmdline.encode_wide().collect();
+    split_wide(&wide)
+        .iter()
+        .map(|arg| OsString::from_wide(arg))
+        .collect()
+}
+
+/// Quote a list of arguments, the first of which is the program name, into a
+/// command line that [`split`] turns back into the same list.
+///
+/// Arguments are only put between double quotes if they contain spaces, tabs,
+/// control characters or double quotes, or characters that are special to
+/// `cmd.exe` such as `&` and `|`. Note that this does not make the result safe
+/// for `cmd.exe` in general because it expands `%VARIABLES%` even between
+/// double quotes.
+///
+/// The program name cannot contain double quotes because the rules for
+/// splitting do not allow them to be escaped. Any double quotes in it are
+/// dropped. This is not a problem in practice because Windows does not allow
+/// double quotes in file names.
+///
+/// # Example
+/// ```
+/// # use argwalker::windows_cmdline::{quote, split};
+/// let args = [r"C:\Program Files\eat.exe", "-v", r#"say "hi""#, r"C:\dir\"];
+/// let cmdline = quote(&args);
+/// assert_eq!(cmdline, r#""C:\Program Files\eat.exe" -v "say \"hi\"" C:\dir\"#);
+/// assert_eq!(split(&cmdline), args);
+/// ```
+pub fn quote<S: AsRef<str>>(args: &[S]) -> String {
+    let wide: Vec<Vec<u16>> = args
+        .iter()
+        .map(|a| a.as_ref().encode_utf16().collect())
+        .collect();
+    String::from_utf16(&quote_wide(&wide)).expect("quoting valid UTF-16 yields valid UTF-16")
+}
+
+/// Like [`quote`] but for arguments given as UTF-16 code units. Unpaired
+/// surrogates are passed through unchanged.
+pub fn quote_wide<S: AsRef<[u16]>>(args: &[S]) -> Vec<u16> {
+    let mut out = vec![];
+    for (i, arg) in args.iter().enumerate() {
+        let arg = arg.as_ref();
+        if i == 0 {
+            quote_program(arg, &mut out);
+        } else {
+            out.push(SPACE);
+            quote_arg(arg, &mut out);
+        }
+    }
+    out
+}
+
+/// Like [`quote`] but for arguments given as [`OsStr`]s. Only available on
+/// Windows.
+#[cfg(windows)]
+pub fn quote_os<S: AsRef<OsStr>>(args: &[S]) -> OsString {
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+    let wide: Vec<Vec<u16>> = args
+        .iter()
+        .map(|a| a.as_ref().encode_wide().collect())
+        .collect();
+    OsString::from_wide(&quote_wide(&wide))
+}
+
+fn needs_quotes(arg: &[u16]) -> bool {
+    arg.is_empty()
+        || arg
+            .iter()
+            .any(|&u| u < 0x20 || u == 0x7F || b" \"&|<>^()%!".iter().any(|&b| u == b as u16))
+}
+
+fn quote_program(arg: &[u16], out: &mut Vec<u16>) {
+    let arg: Vec<u16> = arg.iter().copied().filter(|&u| u != QUOTE).collect();
+    if needs_quotes(&arg) {
+        out.push(QUOTE);
+        out.extend(arg);
+        out.push(QUOTE);
+    } else {
+        out.extend(arg);
+    }
+}
+
+fn quote_arg(arg: &[u16], out: &mut Vec<u16>) {
+    if !needs_quotes(arg) {
+        out.extend_from_slice(arg);
+        return;
+    }
+
+    out.push(QUOTE);
+    let mut backslashes = 0;
+    for &u in arg {
+        match u {
+            BACKSLASH => backslashes += 1,
+            QUOTE => {
+                // double the backslashes and escape the quote
+                out.extend((0..2 * backslashes + 1).map(|_| BACKSLASH));
+                out.push(QUOTE);
+                backslashes = 0;
+            }
+            _ => {
+                out.extend((0..backslashes).map(|_| BACKSLASH));
+                out.push(u);
+                backslashes = 0;
+            }
+        }
+    }
+    // backslashes before the closing quote must be doubled
+    out.extend((0..2 * backslashes).map(|_| BACKSLASH));
+    out.push(QUOTE);
+}
+
+#[test]
+fn test_split() {
+    fn check(cmdline: &str, expected: &[&str]) {
+        assert_eq!(split(cmdline), expected, "splitting {:?}", cmdline);
+    }
+
+    check("", &[]);
+    check("prog", &["prog"]);
+    check(" prog", &["", "prog"]);
+    check("prog  \t a", &["prog", "a"]);
+    check("prog a ", &["prog", "a"]);
+
+    // program name
+    check(
+        r#""C:\Program Files\eat.exe" a"#,
+        &[r"C:\Program Files\eat.exe", "a"],
+    );
+    check(
+        r#"C:\"Program Files"\eat.exe a"#,
+        &[r"C:\Program Files\eat.exe", "a"],
+    );
+    check(r#"prog\" a"#, &[r"prog\ a"]);
+    check(r#""prog"#, &["prog"]);
+
+    // examples from the Microsoft documentation
+    check(r#"prog "a b c" d e"#, &["prog", "a b c", "d", "e"]);
+    check(r#"prog "ab\"c" "\\" d"#, &["prog", r#"ab"c"#, r"\", "d"]);
+    check(r#"prog a\\\b d"e f"g h"#, &["prog", r"a\\\b", "de fg", "h"]);
+    check(r#"prog a\\\"b c d"#, &["prog", r#"a\"b"#, "c", "d"]);
+    check(r#"prog a\\\\"b c" d e"#, &["prog", r"a\\b c", "d", "e"]);
+    check(r#"prog a"b"" c d"#, &["prog", r#"ab" c d"#]);
+
+    // empty arguments and quotes at the end
+    check(r#"prog "" """#, &["prog", "", ""]);
+    check(r#"prog ""#, &["prog", ""]);
+    check(r#"prog a""#, &["prog", "a"]);
+    check(r#"prog """"#, &["prog", r#"""#]);
+    check(r#"prog a\"#, &["prog", r"a\"]);
+
+    // non-ASCII
+    check(
+        "prog ü \u{1F34C}\"\u{1F34C} x\"",
+        &["prog", "ü", "\u{1F34C}\u{1F34C} x"],
+    );
+}
+
+#[test]
+fn test_split_wide_surrogates() {
+    let lone = 0xD800;
+    let cmdline = [b'p' as u16, SPACE, lone, QUOTE, lone, SPACE, QUOTE];
+    assert_eq!(
+        split_wide(&cmdline),
+        vec![vec![b'p' as u16], vec![lone, lone, SPACE]]
+    );
+}
+
+#[test]
+fn test_quote_roundtrip() {
+    let alphabet = ["a", " ", "\t", "\"", "\\", "&", "\u{FC}"];
+    let mut words: Vec<String> = vec![String::new()];
+    let mut prev = words.clone();
+    for _ in 0..4 {
+        prev = prev
+            .iter()
+            .flat_map(|w| alphabet.iter().map(move |a| format!("{}{}", w, a)))
+            .collect();
+        words.extend(prev.iter().cloned());
+    }
+
+    for w in &words {
+        let program = w.replace('"', "");
+        for args in &[
+            vec![program.clone()],
+            vec![program.clone(), w.clone()],
+            vec!["prog".to_string(), w.clone(), w.clone()],
+        ] {
+            let cmdline = quote(args);
+            assert_eq!(&split(&cmdline), args, "quoted as {}", cmdline);
+        }
+    }
+
+    let lone = 0xD800;
+    let args = vec![vec![b'p' as u16], vec![lone, SPACE, QUOTE, lone]];
+    assert_eq!(split_wide(&quote_wide(&args)), args);
+}